@@ -11,6 +11,16 @@ let
       {
         prelude = self.callPackage ./prelude.nix {};
         openssl = self.callPackage ./openssl/default.nix {};
+
+        # Use an additional CryptoNix store. The 'options' are the
+        # same as the ones accepted by CryptoNix (e.g. 'mode' and
+        # 'store-path'). The result provides the same functions as
+        # this library, but bound to the store described by 'options'.
+        open = options: {
+          openssl = self.callPackage ./openssl/default.nix {
+            openssl-primops = builtins.crypto.open options;
+          };
+        };
       }
   );
 in
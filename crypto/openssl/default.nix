@@ -1,10 +1,18 @@
-{ pkgs, lib, prelude, ... }:
+# The 'openssl-primops' argument is the attribute set with the
+# openssl primitives of the CryptoNix instance to be used. By default,
+# the instance configured via the Nix settings is used.
+{ pkgs, lib, prelude, openssl-primops ? builtins.crypto.openssl, ... }:
 let
   modules = lib.makeScope pkgs.newScope (self: {
-    inherit prelude;
+    inherit prelude openssl-primops;
     main = self.callPackage ./main.nix {};
     x509-params-type = self.callPackage ./x509-params-type.nix {};
     private-key-spec-type = self.callPackage ./private-key-spec-type.nix {};
+    symmetric-key-spec-type = self.callPackage ./symmetric-key-spec-type.nix {};
+    openpgp-key-spec-type = self.callPackage ./openpgp-key-spec-type.nix {};
+    minisign-key-spec-type = self.callPackage ./minisign-key-spec-type.nix {};
+    nix-cache-key-spec-type = self.callPackage ./nix-cache-key-spec-type.nix {};
+    password-spec-type = self.callPackage ./password-spec-type.nix {};
   });
 in
   modules.main
@@ -1,8 +1,8 @@
-{ pkgs, prelude, private-key-spec-type, x509-params-type, ... }@module:
+{ pkgs, prelude, private-key-spec-type, symmetric-key-spec-type, openpgp-key-spec-type, minisign-key-spec-type, nix-cache-key-spec-type, password-spec-type, x509-params-type, openssl-primops, ... }@module:
 let
   inherit (pkgs) lib;
   inherit (lib) types;
-  inherit (builtins.crypto) openssl;
+  openssl = openssl-primops;
   type-checker = prelude.type-checker {
     file = "${./openssl.nix}";
   };
@@ -29,15 +29,85 @@ let
       certificate-pem = openssl.x509-pem x509-params-all;
     }
   ;
+  to-key-ref = key-spec: {
+    key-identity = to-key-identity key-spec.attrs;
+    key-type = key-spec.type;
+  } // lib.filterAttrs (_: value: value != null) (key-spec.params or {});
   private-key-impl = key-spec:
   let
-    key-ref = {
-      key-identity = to-key-identity key-spec.attrs;
-      key-type = key-spec.type;
-    };
+    key-ref = to-key-ref key-spec;
   in
     {
+      # The reference to the key used by the primitives, for example
+      # to list the key in the 'keys' of 'jwks'.
+      inherit key-ref;
+
       public-key-pem = openssl.public-key-pem key-ref;
+
+      # Like 'public-key-pem', but returns the result of the
+      # 'try-public-key-pem' primitive, which can be inspected
+      # to degrade gracefully, for example if no store is available.
+      try-public-key-pem = openssl.try-public-key-pem key-ref;
+
+      # The raw public key of X25519, X448, Ed25519 and Ed448 keys,
+      # for example to configure a WireGuard peer.
+      public-key-raw-base64 = openssl.public-key (key-ref // { encoding = "raw-base64"; });
+      public-key-raw-hex = openssl.public-key (key-ref // { encoding = "raw-hex"; });
+
+      # Other representations of the same public key.
+      public-key-der-base64 = openssl.public-key (key-ref // { encoding = "der-base64"; });
+      public-key-jwk = builtins.fromJSON (openssl.public-key (key-ref // { encoding = "jwk"; }));
+      public-key-jwk-thumbprint = openssl.public-key (key-ref // { encoding = "jwk-thumbprint"; });
+      public-key-openssh = openssl.public-key (key-ref // { encoding = "openssh"; });
+
+      # Fingerprints of the public key: the base64 SHA-256 hash of the
+      # 'SubjectPublicKeyInfo' (RFC 7469) and the subject key identifier
+      # placed by CryptoNix in the certificates of this key.
+      public-key-sha256-pin = openssl.public-key (key-ref // { encoding = "sha256-pin"; });
+      public-key-subject-key-identifier = openssl.public-key (key-ref // { encoding = "subject-key-identifier"; });
+
+      # A detached signature over 'data', which is either a string or a
+      # path whose contents are signed. By default, the usual algorithm
+      # of the key type is used and the signature is encoded as base64.
+      sign = { data, algorithm ? null, encoding ? null }:
+        openssl.sign (key-ref // { inherit data algorithm encoding; })
+      ;
+
+      # Encrypt 'plaintext' (a string or a path) to this key. The
+      # ciphertext is safe to put in the Nix store and can only be
+      # decrypted with 'cryptonix decrypt' on a host holding the key.
+      encrypt = { plaintext, encoding ? null }:
+        openssl.encrypt (key-ref // { inherit plaintext encoding; })
+      ;
+
+      # A JWS in the compact serialization over 'payload' (a string or
+      # a path). The 'algorithm' is a JWS name such as "ES256" and
+      # defaults to the usual one of the key type. The 'kid' header
+      # defaults to the JWK thumbprint of the key, as in 'jwks'.
+      jws = { payload, algorithm ? null, type ? null, key-id ? null }:
+        openssl.jws-sign (key-ref // { inherit payload algorithm type key-id; })
+      ;
+
+      # A JWT with the given 'claims', an attribute set of JSON values,
+      # and the registered claims passed as arguments. The dates are in
+      # the RFC 3339 format and are converted to the number of seconds
+      # since the epoch. Signing the same claims again gives the same JWT.
+      jwt = {
+        claims ? {},
+        issuer ? null,
+        subject ? null,
+        audience ? null,
+        issued-at ? null,
+        not-before ? null,
+        expires-at ? null,
+        jwt-id ? null,
+        algorithm ? null
+      }:
+        openssl.jwt-sign (key-ref // {
+          inherit claims issuer subject audience issued-at not-before expires-at jwt-id algorithm;
+        })
+      ;
+
       x509 =
         type-checker.function
         [ { name = "x509-params"; type = x509-params-type; } ]
@@ -51,5 +121,198 @@ let
     [ { name = "key-spec"; type = private-key-spec-type; } ]
     private-key-impl
   ;
+
+  symmetric-key-impl = key-spec:
+  let
+    key-ref = {
+      key-identity = to-key-identity key-spec.attrs;
+    } // lib.optionalAttrs (key-spec.length != null) { inherit (key-spec) length; }
+      // lib.optionalAttrs (key-spec.labels != [ ]) { inherit (key-spec) labels; };
+  in
+    {
+      # The child key derived from this key with 'label'. Children are
+      # derived with HKDF rather than stored, and have the same length.
+      derive = label:
+        symmetric-key-impl (key-spec // { labels = key-spec.labels ++ [ label ]; })
+      ;
+
+      # The HMAC of 'message' (a string or a path) with this key. The
+      # 'hash' defaults to "sha256" and the 'encoding' to "hex".
+      hmac = { message, hash ? null, encoding ? null }:
+        openssl.hmac (key-ref // { inherit message hash encoding; })
+      ;
+      # Encrypt 'plaintext' (a string or a path) with this key. The
+      # same plaintext always gives the same ciphertext, so the result
+      # keeps derivations reproducible. The 'associated-data' is
+      # authenticated but not encrypted, and is required to decrypt.
+      # The 'algorithm' defaults to "aes-256-siv" and the 'encoding'
+      # to "base64". Only the cryptonix command can decrypt.
+      encrypt = { plaintext, associated-data ? null, algorithm ? null, encoding ? null }:
+        openssl.symmetric-encrypt (key-ref // { inherit plaintext associated-data algorithm encoding; })
+      ;
+    }
+  ;
+
+  symmetric-key =
+    type-checker.function
+    [ { name = "key-spec"; type = symmetric-key-spec-type; } ]
+    symmetric-key-impl
+  ;
+
+  openpgp-key-impl = key-spec:
+  let
+    openpgp-ref = {
+      primary-key = to-key-ref key-spec.primary-key;
+      subkey = if key-spec.subkey == null then null else to-key-ref key-spec.subkey;
+      inherit (key-spec) user-id creation-time;
+    };
+  in
+    {
+      # The ASCII armored certificate (transferable public key), to be
+      # imported with 'gpg --import' or published on a keyserver.
+      public-key = openssl.openpgp-public-key openpgp-ref;
+
+      # The fingerprint of the primary key, as 40 hexadecimal digits.
+      fingerprint = openssl.openpgp-fingerprint openpgp-ref;
+
+      # An ASCII armored detached signature over 'data' (a string or a
+      # path), checked with 'gpg --verify'. The signature is dated at
+      # 'date' in the RFC 3339 format, or at the creation time of the key.
+      sign = { data, date ? null }:
+        openssl.openpgp-sign (openpgp-ref // { inherit data date; })
+      ;
+    }
+  ;
+
+  openpgp-key =
+    type-checker.function
+    [ { name = "key-spec"; type = openpgp-key-spec-type; } ]
+    openpgp-key-impl
+  ;
+
+  minisign-key-impl = key-spec:
+  let
+    key-ref = to-key-ref { inherit (key-spec) attrs; type = "ed25519"; };
+  in
+    {
+      # The base64 public key, such as 'RWQ...', as passed to
+      # 'minisign -V -P' and written to the second line of a
+      # minisign public key file.
+      public-key = openssl.minisign-public-key (key-ref // { encoding = "minisign"; });
+
+      # The contents of a signify public key file, for 'signify -V -p'.
+      signify-public-key = openssl.minisign-public-key (key-ref // { encoding = "signify"; });
+
+      # The contents of a minisign signature file over 'data' (a string
+      # or a path). The 'trusted-comment' is signed too, and defaults to
+      # the empty comment rather than a timestamp, so signing the same
+      # data again gives the same signature.
+      sign = { data, trusted-comment ? null, untrusted-comment ? null }:
+        openssl.minisign-sign (key-ref // { inherit data trusted-comment untrusted-comment; })
+      ;
+    }
+  ;
+
+  minisign-key =
+    type-checker.function
+    [ { name = "key-spec"; type = minisign-key-spec-type; } ]
+    minisign-key-impl
+  ;
+
+  nix-cache-key-impl = key-spec:
+  let
+    key-ref = to-key-ref { inherit (key-spec) attrs; type = "ed25519"; } // { inherit (key-spec) name; };
+    sign-fingerprint = fingerprint:
+      openssl.nix-cache-sign (key-ref // { inherit fingerprint; })
+    ;
+  in
+    {
+      # The public key as listed in 'trusted-public-keys',
+      # i.e. '<name>:<base64 public key>'.
+      public-key = openssl.nix-cache-public-key key-ref;
+
+      # The 'Sig' of a narinfo, given the fingerprint of the store path:
+      # '1;<store path>;sha256:<nix32 hash>;<nar size>;<references>'.
+      inherit sign-fingerprint;
+
+      # Like 'sign-fingerprint', but the fingerprint is built from the
+      # fields of the narinfo. The 'nar-hash' may use any format known
+      # to 'builtins.convertHash', such as the SRI format.
+      sign-path = { store-path, nar-hash, nar-size, references ? [] }:
+        let
+          nar-hash-nix32 = builtins.convertHash {
+            hash = nar-hash;
+            hashAlgo = "sha256";
+            toHashFormat = "nix32";
+          };
+        in
+          sign-fingerprint (lib.concatStringsSep ";" [
+            "1"
+            store-path
+            "sha256:${nar-hash-nix32}"
+            (toString nar-size)
+            (lib.concatStringsSep "," references)
+          ])
+      ;
+    }
+  ;
+
+  nix-cache-key =
+    type-checker.function
+    [ { name = "key-spec"; type = nix-cache-key-spec-type; } ]
+    nix-cache-key-impl
+  ;
+
+  password-impl = key-spec:
+  let
+    key-ref = {
+      key-identity = to-key-identity key-spec.attrs;
+    } // lib.filterAttrs (_: value: value != null) { inherit (key-spec) length alphabet; };
+    hash = { scheme ? null }:
+      openssl.password-hash (key-ref // { inherit scheme; })
+    ;
+  in
+    {
+      # The crypt hash of the password with the given 'scheme': "yescrypt"
+      # (the default), "sha512-crypt", "bcrypt" or "argon2". The salt is
+      # derived from the identity, so the hash is stable.
+      inherit hash;
+
+      # The yescrypt hash, for 'users.users.<name>.hashedPassword'.
+      hashed-password = hash {};
+    }
+  ;
+
+  password =
+    type-checker.function
+    [ { name = "key-spec"; type = password-spec-type; } ]
+    password-impl
+  ;
+
+  # Check a detached signature over 'data' (a string or a path) against
+  # a PEM encoded public key or certificate. The 'algorithm' and the
+  # 'encoding' default to the ones used by 'sign'. Verifying does not
+  # involve the store, so any signature can be checked.
+  verify = { public-key, data, signature, algorithm ? null, encoding ? null }:
+    openssl.verify { inherit public-key data signature algorithm encoding; }
+  ;
+
+  # A JWK set with the public keys of 'keys', to be served by an OpenID
+  # Connect provider for example. Each item is either a private key or
+  # an attribute set '{ key, algorithm }' to publish another algorithm
+  # than the default one of the key type. The 'kid' of each key is its
+  # JWK thumbprint, which is also the 'kid' of the tokens it signs.
+  jwks = keys:
+    builtins.fromJSON (openssl.jwks {
+      keys = lib.map (item: if item ? key then item.key.key-ref // { inherit (item) algorithm; } else item.key-ref) keys;
+    })
+  ;
+
+  # Check that the PEM encoded 'certificate' was issued by 'issuer', which
+  # is either a PEM encoded public key or certificate. If a 'date' in the
+  # RFC 3339 format is given, the certificate must be valid at that date.
+  x509-verify = { certificate, issuer, date ? null }:
+    openssl.x509-verify { inherit certificate issuer date; }
+  ;
 in
-  { inherit private-key; }
+  { inherit private-key symmetric-key openpgp-key minisign-key nix-cache-key password verify x509-verify jwks; }
@@ -0,0 +1,19 @@
+{ pkgs, lib, ... }:
+let
+  inherit (lib) types;
+  minisign-key-spec-type =
+    types.submodule {
+      options = {
+        attrs = lib.mkOption {
+          description = ''
+            The attributes which identify the Ed25519 key, in the same
+            way as for private keys. The key is reserved to minisign: it
+            is not the 'ed25519' private key with these attributes.
+          '';
+          type = types.attrsOf types.str;
+        };
+      };
+    }
+  ;
+in
+  minisign-key-spec-type
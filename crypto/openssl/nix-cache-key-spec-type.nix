@@ -0,0 +1,29 @@
+{ pkgs, lib, ... }:
+let
+  inherit (lib) types;
+  nix-cache-key-spec-type =
+    types.submodule {
+      options = {
+        attrs = lib.mkOption {
+          description = ''
+            The attributes which identify the Ed25519 key, in the same
+            way as for private keys. The key is reserved to the binary
+            cache: it is not the 'ed25519' private key with these
+            attributes. The secret key is exported for the builders with
+            'cryptonix export-nix-cache-key'.
+          '';
+          type = types.attrsOf types.str;
+        };
+        name = lib.mkOption {
+          description = ''
+            The name of the key, such as 'cache.example.org-1', which
+            prefixes the public key and the signatures. It is not part
+            of the identity of the key.
+          '';
+          type = types.strMatching "[^:[:space:]]+";
+        };
+      };
+    }
+  ;
+in
+  nix-cache-key-spec-type
@@ -0,0 +1,47 @@
+{ pkgs, lib, private-key-spec-type, ... }:
+let
+  inherit (lib) types;
+  openpgp-key-spec-type =
+    types.submodule {
+      options = {
+        primary-key = lib.mkOption {
+          description = ''
+            The private key which certifies the OpenPGP key and makes
+            its signatures. It must be able to sign, hence it is either
+            a 'rsa', an 'ec' or an 'ed25519' key. The key is reserved to
+            OpenPGP: it is not the key of a 'private-key' with the same
+            attributes, which can sign arbitrary data.
+          '';
+          type = private-key-spec-type;
+        };
+        subkey = lib.mkOption {
+          description = ''
+            The private key of the encryption subkey, bound to the
+            primary key. It is either a 'rsa', an 'ec' or a 'x25519'
+            key, reserved to OpenPGP as the primary key. No subkey is
+            published if it is null.
+          '';
+          default = null;
+          type = types.nullOr private-key-spec-type;
+        };
+        user-id = lib.mkOption {
+          description = ''
+            The user ID certified by the primary key, usually of the
+            form 'Name <email>'.
+          '';
+          type = types.nonEmptyStr;
+        };
+        creation-time = lib.mkOption {
+          description = ''
+            The creation time of the keys, in the RFC 3339 format. It
+            is part of the fingerprint, and the self-signatures are made
+            at that time, so the certificate is always the same. It must
+            not change once the certificate is published.
+          '';
+          type = types.str;
+        };
+      };
+    }
+  ;
+in
+  openpgp-key-spec-type
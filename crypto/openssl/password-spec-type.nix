@@ -0,0 +1,42 @@
+{ pkgs, lib, ... }:
+let
+  inherit (lib) types;
+  password-spec-type =
+    types.submodule {
+      options = {
+        attrs = lib.mkOption {
+          description = ''
+            The attributes which identify the password, in the same
+            way as for private keys. The password is generated once
+            and never exposed to the nix language. Only its hashes
+            are, and 'cryptonix export-password' prints it.
+          '';
+          type = types.attrsOf types.str;
+        };
+        length = lib.mkOption {
+          description = ''
+            The number of characters of the password. The length is
+            part of the identity of the password. Defaults to 24.
+          '';
+          default = null;
+          type = types.nullOr (types.ints.between 8 256);
+        };
+        alphabet = lib.mkOption {
+          description = ''
+            The characters from which the password is drawn. The
+            alphabet is part of the identity of the password.
+            Currently supported:
+              * alphanumeric (the default)
+              * lowercase-alphanumeric
+              * digits
+              * hex
+              * printable (the printable ASCII characters but the space)
+          '';
+          default = null;
+          type = types.nullOr (types.enum [ "alphanumeric" "lowercase-alphanumeric" "digits" "hex" "printable" ]);
+        };
+      };
+    }
+  ;
+in
+  password-spec-type
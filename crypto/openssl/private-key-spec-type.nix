@@ -34,8 +34,44 @@ let
             The cryptographic protocol to be used to generate
             the private key. Currently supported:
               * rsa
+              * ec (ECDSA with the curve given by the 'curve' parameter)
+              * ec-p256 (ECDSA with the NIST P-256 curve)
+              * ec-p384 (ECDSA with the NIST P-384 curve)
+              * ec-p521 (ECDSA with the NIST P-521 curve)
+              * ed25519 (EdDSA with Curve25519)
+              * ed448 (EdDSA with Curve448)
+              * x25519 (Diffie-Hellman key agreement, cannot sign)
+              * x448 (Diffie-Hellman key agreement, cannot sign)
           '';
-          type = types.oneOf (lib.map types.strMatching [ "rsa" ]);
+          type = types.enum [ "rsa" "ec" "ec-p256" "ec-p384" "ec-p521" "ed25519" "ed448" "x25519" "x448" ];
+        };
+        params = lib.mkOption {
+          description = ''
+            Parameters of the algorithm used to generate the private
+            key. The parameters are part of the identity of the key,
+            hence the same attributes with different parameters refer
+            to different keys. Parameters left unset use their default.
+          '';
+          default = {};
+          type = types.submodule {
+            options = {
+              bits = lib.mkOption {
+                description = "The size in bits of a 'rsa' key. Defaults to 4096.";
+                default = null;
+                type = types.nullOr types.ints.positive;
+              };
+              exponent = lib.mkOption {
+                description = "The public exponent of a 'rsa' key. Defaults to 65537.";
+                default = null;
+                type = types.nullOr types.ints.positive;
+              };
+              curve = lib.mkOption {
+                description = "The curve of an 'ec' key. Defaults to p256.";
+                default = null;
+                type = types.nullOr (types.enum [ "p256" "p384" "p521" ]);
+              };
+            };
+          };
         };
       };
     }
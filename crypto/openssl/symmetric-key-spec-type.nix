@@ -0,0 +1,37 @@
+{ pkgs, lib, ... }:
+let
+  inherit (lib) types;
+  symmetric-key-spec-type =
+    types.submodule {
+      options = {
+        attrs = lib.mkOption {
+          description = ''
+            The attributes which identify the symmetric key, in the
+            same way as for private keys. The key is generated once
+            and never exposed to the nix language. Only values derived
+            from it, such as a HMAC, are.
+          '';
+          type = types.attrsOf types.str;
+        };
+        length = lib.mkOption {
+          description = ''
+            The length of the key in bytes. The length is part of the
+            identity of the key. Defaults to 32.
+          '';
+          default = null;
+          type = types.nullOr (types.ints.between 16 1024);
+        };
+        labels = lib.mkOption {
+          description = ''
+            The labels of the child key derived from the key, one per
+            level of derivation. Children are derived deterministically
+            with HKDF-SHA256 and are never stored.
+          '';
+          default = [ ];
+          type = types.listOf types.nonEmptyStr;
+        };
+      };
+    }
+  ;
+in
+  symmetric-key-spec-type
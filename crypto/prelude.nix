@@ -12,7 +12,17 @@ let
         lib.foldl check-apply func args
     ;
   };
+
+  # Obtain the value of the result of a 'try-' CryptoNix primitive,
+  # which has the form '{ success; value; error = { code; message; }; }'.
+  # If the operation failed, the result of applying 'fallback' to the
+  # error is returned instead.
+  value-or = fallback: result:
+    if result.success
+    then result.value
+    else fallback result.error
+  ;
 in
   {
-    inherit type-checker;
+    inherit type-checker value-or;
   }
@@ -29,6 +29,7 @@ in
     # and the qemu vm.
     test-dev = pkgs.writeScriptBin "nix-crypto-check" ''
       STORE=$(mktemp -d)
+      export CRYPTONIX_TEST_OPEN_STORE=$(mktemp -d)
       nix \
         --extra-experimental-features nix-command \
         --option plugin-files "$PWD/target/debug/libnix_crypto_plugin.so" \
@@ -53,6 +54,7 @@ in
               nix-fetchers
               boost
               cargo
+              libxcrypt
               nixVersions.nix_2_31
             ]
           ;
@@ -67,6 +69,8 @@ in
               runtimeInputs = [ nix-crypto.packages.nix-crypto ];
               text = ''
                 STORE=$(mktemp -d)
+                CRYPTONIX_TEST_OPEN_STORE=$(mktemp -d)
+                export CRYPTONIX_TEST_OPEN_STORE
                 cd ${./.}
                 nix \
                   --extra-experimental-features nix-command \
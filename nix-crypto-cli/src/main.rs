@@ -0,0 +1,343 @@
+mod options;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process::{ExitCode};
+
+// Imports from sister crates
+use nix_crypto_core::args::{CryptoNixSettings};
+use nix_crypto_core::encoding::{TextEncoding};
+use nix_crypto_core::error::{Error, ErrorContext};
+use nix_crypto_core::foundations::{CryptoNix};
+use nix_crypto_core::openssl::{PrivateKeyIdentity};
+use nix_crypto_core::openssl::ffi;
+use nix_crypto_core::openssl::import;
+use nix_crypto_core::password::{PasswordIdentity};
+use nix_crypto_core::symmetric::{AeadAlgorithm, SymmetricKeyIdentity};
+
+// Imports from this crate
+use crate::options::{Options};
+
+const K_USAGE : &str = r#"
+Usage: cryptonix [GLOBAL OPTIONS] <COMMAND> [OPTIONS]
+
+Manage the secrets of a CryptoNix store outside of Nix. Private
+material handled by these commands never passes through Nix.
+
+Global options select the store in the same way as the Nix
+plugin settings of the same name. When they are absent, the
+file "$XDG_CONFIG_HOME/cryptonix/config.toml" is used.
+  --mode <mode>               The mode of the store, e.g. "filesystem".
+  --store-path <path>         The absolute path of the store.
+  --config-file <path>        The absolute path of a configuration file.
+
+Commands:
+  import-key                  Adopt an existing private key under an identity.
+      --type <type>           The key type of the identity, e.g. "rsa".
+      --attr <name=value>     An attribute of the identity. Repeat the option
+                              for every attribute, as in the Nix 'attrs'.
+      --bits <bits>           The 'bits' parameter of the identity.
+      --exponent <exponent>   The 'exponent' parameter of the identity.
+      --curve <curve>         The 'curve' parameter of the identity.
+      --file <path>           The private key in PEM, DER, PKCS#8 or
+                              OpenSSH format.
+      --passphrase-file <path>
+                              A file holding the passphrase of an
+                              encrypted PKCS#8 key.
+
+  decrypt                     Decrypt a value encrypted with 'encrypt' in
+                              Nix and write the plaintext to stdout.
+      --type, --attr, --bits, --exponent, --curve
+                              The identity of the key, as for 'import-key'.
+      --file <path>           The file holding the ciphertext.
+      --encoding <encoding>   The encoding of the ciphertext: "base64"
+                              (the default), "base64url" or "hex".
+
+  export-secret               Write a symmetric key, or a child derived from
+                              it, to stdout. The key is generated if needed.
+      --attr <name=value>     An attribute of the identity, as for 'import-key'.
+      --length <length>       The 'length' parameter of the identity.
+      --label <label>         A label of the derived child. Repeat the option
+                              for every level, as in the Nix 'labels'.
+      --encoding <encoding>   The encoding of the key: "base64" (the
+                              default), "base64url" or "hex".
+
+  decrypt-secret              Decrypt a value encrypted with the 'encrypt'
+                              function of a symmetric key in Nix and write
+                              the plaintext to stdout.
+      --attr, --length, --label
+                              The identity of the key, as for 'export-secret'.
+      --algorithm <algorithm> The AEAD algorithm: "aes-256-siv" (the
+                              default) or "aes-256-gcm-siv".
+      --associated-data <data>
+                              The associated data given to 'encrypt'.
+      --file <path>           The file holding the ciphertext.
+      --encoding <encoding>   The encoding of the ciphertext, as for 'decrypt'.
+
+  export-password             Write a password to stdout. The password is
+                              generated if needed, as Nix only obtains its
+                              hashes.
+      --attr <name=value>     An attribute of the identity, as for 'import-key'.
+      --length <length>       The 'length' parameter of the identity.
+      --alphabet <alphabet>   The 'alphabet' parameter of the identity.
+
+  export-nix-cache-key        Write the secret key of a Nix binary cache key
+                              to stdout, in the format of the files listed in
+                              the 'secret-key-files' setting of Nix.
+      --attr <name=value>     An attribute of the identity, as for 'import-key'.
+      --name <name>           The name of the key, e.g. "cache.example.org-1".
+"#;
+
+const K_GLOBAL_OPTIONS : &[&str] = &["mode", "store-path", "config-file"];
+
+const K_IDENTITY_OPTIONS : &[&str] = &["type", "attr", "bits", "exponent", "curve"];
+
+const K_SYMMETRIC_IDENTITY_OPTIONS : &[&str] = &["attr", "length", "label"];
+
+/// Percent-encode the characters which have a special
+/// meaning in "extra-cryptonix-args".
+fn percent_encode(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('&', "%26")
+        .replace('=', "%3D")
+}
+
+/// Open the store selected by the global options.
+fn open_store(global: &Options) -> CryptoNix {
+
+    let settings = CryptoNixSettings {
+        extra_args: global.get("config-file")
+            .map(|path| format!("config-file={}", percent_encode(path)))
+            .unwrap_or_default(),
+        mode: global.get("mode").unwrap_or_default().to_string(),
+        store_path: global.get("store-path").unwrap_or_default().to_string()
+    };
+
+    CryptoNix::with_settings(&settings)
+}
+
+/// Build the key id from the '--attr' options of a command,
+/// as the Nix library does from the 'attrs' of a key spec.
+fn key_id(options: &Options) -> Result<String, Error> {
+
+    let attrs = options.get_all("attr").into_iter()
+        .map(|attr| attr.split_once('=')
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .ok_or(Error::invalid_argument(
+                format!("The option '--attr' must be of the form 'name=value', found '{attr}'.")
+            ))
+        )
+        .collect::<Result<Vec<(String, String)>, Error>>()?;
+
+    if attrs.is_empty() {
+        return Err(Error::invalid_argument("At least one '--attr' option is required.".to_string()));
+    }
+
+    Ok(ffi::key_id_from_attrs(&attrs))
+}
+
+/// Build the identity of a private key from the options
+/// of a command, as the Nix library does from a key spec.
+fn private_key_identity(options: &Options) -> Result<PrivateKeyIdentity, Error> {
+
+    Ok(PrivateKeyIdentity {
+        key_type: options.required("type")?.to_string(),
+        key_id: key_id(options)?,
+        rsa_bits: options.get_u32("bits")?,
+        rsa_exponent: options.get_u32("exponent")?,
+        ec_curve: options.get("curve").map(|curve| curve.to_string())
+    })
+}
+
+/// Build the identity of a symmetric key from the options
+/// of a command, as the Nix library does from a key spec.
+fn symmetric_key_identity(options: &Options) -> Result<SymmetricKeyIdentity, Error> {
+
+    Ok(SymmetricKeyIdentity {
+        key_id: key_id(options)?,
+        length: options.get_u32("length")?,
+        labels: options.get_all("label").into_iter().map(|label| label.to_string()).collect()
+    })
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|e| Error::invalid_argument(
+        format!("Could not read the file '{path}': {e}")
+    ))
+}
+
+fn import_key(crypto_nix: &CryptoNix, args: &[String]) -> Result<(), Error> {
+
+    let known = [K_IDENTITY_OPTIONS, &["file", "passphrase-file"]].concat();
+    let (options, _) = Options::parse(args, &known, &["attr"])?;
+
+    let key_identity = private_key_identity(&options)?;
+    let bytes = read_file(options.required("file")?)?;
+
+    let passphrase = options.get("passphrase-file")
+        .map(|path| read_file(path).map(|mut passphrase| {
+            // Editors usually end files with a newline which is
+            // not part of the passphrase.
+            while passphrase.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+                passphrase.pop();
+            }
+            passphrase
+        }))
+        .transpose()?;
+
+    let key = import::read_private_key(&bytes, passphrase.as_deref())
+        .with_context(|| format!("while reading the private key from '{}'", options.required("file").unwrap_or_default()))?;
+
+    crypto_nix.openssl_import_private_key(&key_identity, &key)?;
+
+    eprintln!("Imported the private key as the identity '{}' of type '{}'.", key_identity.key_id, key_identity.key_type);
+    Ok(())
+}
+
+/// Read the '--encoding' option, which defaults to base64.
+fn text_encoding(options: &Options) -> Result<TextEncoding, Error> {
+    Ok(options.get("encoding")
+        .map(TextEncoding::try_from)
+        .transpose()
+        .context("while reading the option '--encoding'")?
+        .unwrap_or_default())
+}
+
+/// Read the encoded ciphertext held by 'file'.
+fn read_ciphertext(file: &str, encoding: TextEncoding) -> Result<Vec<u8>, Error> {
+
+    let text = String::from_utf8(read_file(file)?).map_err(|_| Error::invalid_argument(
+        format!("The file '{file}' does not hold an encoded ciphertext.")
+    ))?;
+    encoding.decode(text.trim())
+        .with_context(|| format!("while reading the ciphertext from '{file}'"))
+}
+
+fn write_plaintext(plaintext: &[u8]) -> Result<(), Error> {
+
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(plaintext)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| Error::internal(format!("Could not write the plaintext: {e}")))
+}
+
+fn decrypt(crypto_nix: &CryptoNix, args: &[String]) -> Result<(), Error> {
+
+    let known = [K_IDENTITY_OPTIONS, &["file", "encoding"]].concat();
+    let (options, _) = Options::parse(args, &known, &["attr"])?;
+
+    let key_identity = private_key_identity(&options)?;
+    let encoding = text_encoding(&options)?;
+
+    let ciphertext = read_ciphertext(options.required("file")?, encoding)?;
+
+    let plaintext = crypto_nix.openssl_decrypt(&key_identity, &ciphertext)?;
+    write_plaintext(&plaintext)
+}
+
+fn decrypt_secret(crypto_nix: &CryptoNix, args: &[String]) -> Result<(), Error> {
+
+    let known = [K_SYMMETRIC_IDENTITY_OPTIONS, &["algorithm", "associated-data", "file", "encoding"]].concat();
+    let (options, _) = Options::parse(args, &known, &["attr", "label"])?;
+
+    let key_identity = symmetric_key_identity(&options)?;
+    let algorithm = options.get("algorithm")
+        .map(AeadAlgorithm::try_from)
+        .transpose()
+        .context("while reading the option '--algorithm'")?
+        .unwrap_or_default();
+    let encoding = text_encoding(&options)?;
+
+    let ciphertext = read_ciphertext(options.required("file")?, encoding)?;
+
+    let plaintext = crypto_nix.symmetric_decrypt(
+        &key_identity,
+        algorithm,
+        &ciphertext,
+        options.get("associated-data").map(|associated_data| associated_data.as_bytes())
+    )?;
+    write_plaintext(&plaintext)
+}
+
+fn export_secret(crypto_nix: &CryptoNix, args: &[String]) -> Result<(), Error> {
+
+    let known = [K_SYMMETRIC_IDENTITY_OPTIONS, &["encoding"]].concat();
+    let (options, _) = Options::parse(args, &known, &["attr", "label"])?;
+
+    let key_identity = symmetric_key_identity(&options)?;
+    let encoding = text_encoding(&options)?;
+
+    let key = crypto_nix.symmetric_key(&key_identity)?;
+
+    println!("{}", encoding.encode(key.as_bytes()));
+    Ok(())
+}
+
+fn export_password(crypto_nix: &CryptoNix, args: &[String]) -> Result<(), Error> {
+
+    let (options, _) = Options::parse(args, &["attr", "length", "alphabet"], &["attr"])?;
+
+    let password_identity = PasswordIdentity {
+        key_id: key_id(&options)?,
+        length: options.get_u32("length")?,
+        alphabet: options.get("alphabet").map(|alphabet| alphabet.to_string())
+    };
+
+    let password = crypto_nix.password(&password_identity)?;
+
+    println!("{}", password.as_str());
+    Ok(())
+}
+
+fn export_nix_cache_key(crypto_nix: &CryptoNix, args: &[String]) -> Result<(), Error> {
+
+    let (options, _) = Options::parse(args, &["attr", "name"], &["attr"])?;
+
+    // The identity is the one of an 'ed25519' private key with the
+    // same attributes, which 'nix_cache_secret_key' reserves to the
+    // Nix binary cache keys, as the Nix library does.
+    let key_identity = PrivateKeyIdentity::new("ed25519", &key_id(&options)?);
+
+    let secret_key = crypto_nix.nix_cache_secret_key(&key_identity, options.required("name")?)?;
+
+    println!("{secret_key}");
+    Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), Error> {
+
+    let (global, rest) = Options::parse(args, K_GLOBAL_OPTIONS, &[])?;
+
+    let Some((command, command_args)) = rest.split_first() else {
+        return Err(Error::invalid_argument(format!("No command was given.\n{K_USAGE}")));
+    };
+
+    match command.as_str() {
+        "import-key" => import_key(&open_store(&global), command_args),
+        "decrypt" => decrypt(&open_store(&global), command_args),
+        "export-secret" => export_secret(&open_store(&global), command_args),
+        "decrypt-secret" => decrypt_secret(&open_store(&global), command_args),
+        "export-password" => export_password(&open_store(&global), command_args),
+        "export-nix-cache-key" => export_nix_cache_key(&open_store(&global), command_args),
+        "help" => {
+            println!("{K_USAGE}");
+            Ok(())
+        },
+        other => Err(Error::invalid_argument(format!("Unknown command '{other}'.\n{K_USAGE}")))
+    }
+}
+
+fn main() -> ExitCode {
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("cryptonix: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
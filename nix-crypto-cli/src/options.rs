@@ -0,0 +1,89 @@
+use nix_crypto_core::error::{Error};
+
+/// The options supplied to the program or to one of its
+/// commands, in the order in which they were given. Every
+/// option is of the form "--name value" or "--name=value".
+pub struct Options {
+    values: Vec<(String, String)>
+}
+
+impl Options {
+
+    /// Parse the options at the start of 'args', stopping at the
+    /// first argument which is not an option. Returns the options
+    /// together with the remaining arguments. Options which are not
+    /// listed in 'known' are rejected, as are repeated options which
+    /// are not listed in 'repeatable'.
+    pub fn parse<'a>(
+        args: &'a [String],
+        known: &[&str],
+        repeatable: &[&str]
+    ) -> Result<(Options, &'a [String]), Error> {
+
+        let mut values: Vec<(String, String)> = Vec::new();
+        let mut rest = args;
+
+        while let Some((arg, tail)) = rest.split_first() {
+
+            let Some(option) = arg.strip_prefix("--") else {
+                break;
+            };
+
+            let (name, value, tail) = match option.split_once('=') {
+                Some((name, value)) => (name, value.to_string(), tail),
+                None => match tail.split_first() {
+                    Some((value, tail)) => (option, value.clone(), tail),
+                    None => return Err(Error::invalid_argument(
+                        format!("The option '--{option}' requires a value.")
+                    ))
+                }
+            };
+
+            if !known.contains(&name) {
+                let names: Vec<String> = known.iter().map(|name| format!("--{name}")).collect();
+                return Err(Error::invalid_argument(format!(
+                    "Unknown option '--{name}'. The known options are: {}.",
+                    names.join(", ")
+                )));
+            }
+
+            if !repeatable.contains(&name) && values.iter().any(|(existing, _)| existing == name) {
+                return Err(Error::invalid_argument(
+                    format!("The option '--{name}' was supplied more than once.")
+                ));
+            }
+
+            values.push((name.to_string(), value));
+            rest = tail;
+        }
+
+        Ok((Options { values }, rest))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.values.iter()
+            .filter(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn required(&self, name: &str) -> Result<&str, Error> {
+        self.get(name).ok_or(Error::invalid_argument(
+            format!("The option '--{name}' is required.")
+        ))
+    }
+
+    pub fn get_u32(&self, name: &str) -> Result<Option<u32>, Error> {
+        self.get(name)
+            .map(|value| value.parse::<u32>().map_err(|_| Error::invalid_argument(
+                format!("The option '--{name}' must be a non-negative integer, found '{value}'.")
+            )))
+            .transpose()
+    }
+}
@@ -1,41 +1,329 @@
-use regex::Regex;
 use std::collections::HashMap;
+use std::path::{Path};
 
+use crate::config;
 use crate::error::{Error};
 
+const K_MODE : &str = "mode";
+const K_STORE_PATH : &str = "store-path";
+const K_CONFIG_FILE : &str = "config-file";
+const K_KEY_POOL_SIZE : &str = "key-pool-size";
+const K_FILESYSTEM_MODE : &str = "filesystem";
+
+/// The largest number of keys kept in the pool of each algorithm.
+const K_MAX_KEY_POOL_SIZE : usize = 256;
+
+/// All the options understood by 'CryptoNix'. Any other
+/// key supplied by one of the configuration sources is
+/// reported as an error.
+const K_KNOWN_OPTIONS : &[&str] = &[K_MODE, K_STORE_PATH, K_CONFIG_FILE, K_KEY_POOL_SIZE];
+
+/// The options which may appear in a configuration file. A
+/// file cannot reference another configuration file.
+const K_CONFIG_FILE_OPTIONS : &[&str] = &[K_MODE, K_STORE_PATH, K_KEY_POOL_SIZE];
+
+/// All the modes in which 'CryptoNix' can operate.
+const K_KNOWN_MODES : &[&str] = &[K_FILESYSTEM_MODE];
+
+const K_USAGE : &str = r#"
+CryptoNix needs to be configured in order to be used. The
+configuration is read from the following sources, where an
+option supplied by a source takes precedence over the same
+option supplied by any of the sources listed after it:
+  1. The typed Nix settings "cryptonix-mode" and
+     "cryptonix-store-path".
+  2. The "--option extra-cryptonix-args" flag. The options
+     supplied via this flag are key/value sets formatted like
+     "key1=value1&key2=value2". Keys and values are
+     percent-decoded, so a '&' is written as "%26", a '='
+     as "%3D" and a '%' as "%25".
+  3. The TOML file referenced by the "config-file" option of
+     "extra-cryptonix-args" or, if that option is absent, the
+     file "$XDG_CONFIG_HOME/cryptonix/config.toml" when it exists.
+     The file itself cannot contain the "config-file" option.
+The known options are "mode", "store-path", "config-file" and
+"key-pool-size". Paths must be absolute. When "key-pool-size" is
+set to a number greater than 0, up to that many private keys of
+every algorithm in use are generated ahead of time by a background
+thread, so new identities do not wait for slow key generation.
+Below are concrete examples:
+    nix --option cryptonix-mode filesystem --option cryptonix-store-path /tmp/secrets
+    nix --option extra-cryptonix-args "mode=filesystem&store-path=/tmp/secrets"
+    nix --option extra-cryptonix-args "config-file=/etc/cryptonix.toml"
+"#;
+
+/// The options obtained from a single configuration source
+/// once they have been parsed and checked for unknown keys.
+type Options = HashMap<String, String>;
+
+/// Accumulates the problems found while reading the configuration
+/// of 'CryptoNix'. Parsing does not stop at the first problem so
+/// that all of them can be reported to the user at once.
+#[derive(Default)]
+struct Problems(Vec<String>);
+
+impl Problems {
+
+    fn push(&mut self, problem: String) {
+        self.0.push(problem);
+    }
+
+    fn into_result<T>(self, value: T) -> Result<T, Error> {
+
+        if self.0.is_empty() {
+            return Ok(value);
+        }
+
+        let problems: Vec<String> =
+            self.0.iter().map(|p| format!("  * {}", p)).collect();
+
+        Err(Error::configuration(
+            format!(
+                "CryptoNix could not be configured due to the following problems:\n{}\n{}",
+                problems.join("\n"),
+                K_USAGE
+            )
+        ))
+    }
+}
+
+/// Compute the edit distance between two strings. This is used
+/// to suggest the intended option when an unknown one is found.
+fn edit_distance(a: &str, b: &str) -> usize {
+
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Find the entry among 'candidates' which is closest to 'value',
+/// provided it is close enough to plausibly be a typo.
+fn suggest<'a>(value: &str, candidates: &[&'a str]) -> Option<&'a str> {
+
+    candidates.iter()
+        .map(|candidate| (edit_distance(value, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn did_you_mean(value: &str, candidates: &[&str]) -> String {
+
+    match suggest(value, candidates) {
+        Some(candidate) => format!(" Did you mean '{}'?", candidate),
+        None => format!(" Expected one of: {}.", candidates.join(", "))
+    }
+}
+
+/// Decode the '%XX' escape sequences of a key or value supplied
+/// via "extra-cryptonix-args". The '+' character is not treated
+/// specially as it commonly appears in paths.
+fn percent_decode(value: &str) -> Result<String, String> {
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        let escape = bytes.get(i + 1..i + 3)
+            .and_then(|hex| str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or(format!("The text '{}' contains an invalid percent-encoded sequence at position {}.", value, i))?;
+
+        decoded.push(escape);
+        i += 3;
+    }
+
+    String::from_utf8(decoded)
+        .map_err(|_| format!("The text '{}' does not decode into valid UTF-8.", value))
+}
+
 /// Parse the arguments provided to 'CryptoNix' as a
 /// key/value data structure. The arguments are supplied
 /// to crypto nix via the nix command line as follows:
 /// --option extra-cryptonix-args "arg1=value&arg2=other_value".
-fn parse_args(query: &str) -> HashMap<String, Vec<String>> {
-    let re = Regex::new(r"(?:^|&)([^=&]+)=?([^&]*)").unwrap();
+/// Keys and values are percent-decoded. Malformed pairs, unknown
+/// keys and duplicate keys are reported as problems.
+fn parse_args(query: &str, problems: &mut Problems) -> Options {
+
+    let source = "extra-cryptonix-args";
+    let mut options = Options::new();
 
-    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
 
-    for caps in re.captures_iter(query) {
-        let key = caps.get(1).unwrap().as_str().to_string();
-        let value = caps.get(2).unwrap().as_str().to_string();
+        let Some((raw_key, raw_value)) = pair.split_once('=') else {
+            problems.push(
+                format!("The entry '{}' in '{}' is not formatted as 'key=value'.", pair, source)
+            );
+            continue;
+        };
 
-        map.entry(key)
-            .or_insert_with(Vec::new)
-            .push(value);
+        let (key, value) = match (percent_decode(raw_key), percent_decode(raw_value)) {
+            (Ok(key), Ok(value)) => (key, value),
+            (key, value) => {
+                key.err().into_iter()
+                    .chain(value.err())
+                    .for_each(|e| problems.push(format!("{} (in '{}')", e, source)));
+                continue;
+            }
+        };
+
+        if options.contains_key(&key) {
+            problems.push(
+                format!("The option '{}' was supplied more than once in '{}'. Each option must only be used once.", key, source)
+            );
+            continue;
+        }
+
+        options.insert(key, value);
     }
 
-    map
+    check_known_options(source, &options, K_KNOWN_OPTIONS, problems);
+    options
 }
 
-const K_MODE : &str = "mode";
-const K_STORE_PATH : &str = "store-path";
-const K_FILESYSTEM_MODE : &str = "filesystem";
+/// Report every option of 'source' which is not among the 'known' ones.
+fn check_known_options(source: &str, options: &Options, known: &[&str], problems: &mut Problems) {
 
-const K_USAGE : &str = r#"
-CryptoNix needs to be configured in order to be used. This
-is chieved by using the "--option extra-cryptonix-args" flag
-when running nix. The options supplied via this flag
-are key/value sets formatted like "key1=value1&key2=value2".
-Below is a concrete example:
-    nix --option extra-cryptonix-args "mode=filesystem&store-path=/tmp/secrets"
-"#;
+    let mut unknown: Vec<&String> =
+        options.keys()
+        .filter(|key| !known.contains(&key.as_str()))
+        .collect();
+    unknown.sort();
+
+    for key in unknown {
+        problems.push(
+            format!(
+                "The option '{}' supplied via '{}' is not a known CryptoNix option.{}",
+                key,
+                source,
+                did_you_mean(key, known)
+            )
+        );
+    }
+}
+
+/// Check that the option 'key' holds an absolute path.
+fn check_absolute_path(key: &str, path: &str, problems: &mut Problems) -> bool {
+
+    if path.is_empty() {
+        problems.push(format!("The option '{}' must not be empty.", key));
+        false
+    } else if !Path::new(path).is_absolute() {
+        problems.push(format!("The option '{}' must be an absolute path, found '{}'.", key, path));
+        false
+    } else {
+        true
+    }
+}
+
+/// The settings that Nix supplies to 'CryptoNix'. The 'extra_args'
+/// field contains the value of the "extra-cryptonix-args" option
+/// while the remaining fields hold the typed settings (such as
+/// "cryptonix-mode"). Empty values indicate that the setting has
+/// not been provided.
+#[derive(Default)]
+pub struct CryptoNixSettings {
+    pub extra_args : String,
+    pub mode : String,
+    pub store_path : String
+}
+
+impl CryptoNixSettings {
+
+    pub fn from_args(args: &str) -> Self {
+        CryptoNixSettings { extra_args: args.to_string(), ..Default::default() }
+    }
+
+    /// The options provided via the typed Nix settings. These
+    /// options take precedence over all other sources.
+    fn typed_options(&self) -> Options {
+
+        let typed = [
+            (K_MODE, &self.mode),
+            (K_STORE_PATH, &self.store_path)
+        ];
+
+        typed.into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+}
+
+/// Read the configuration file that applies to the given arguments. If
+/// the 'config-file' option is present, the file it references must exist.
+/// Otherwise, the default configuration file is used if it exists and
+/// 'use_default' is set.
+fn config_file_options(args: &Options, use_default: bool, problems: &mut Problems) -> Options {
+
+    let path = match args.get(K_CONFIG_FILE) {
+        Some(path) if check_absolute_path(K_CONFIG_FILE, path, problems) =>
+            Path::new(path).to_path_buf(),
+        Some(_) => return Options::new(),
+        None => match config::default_config_file() {
+            Some(path) if use_default && path.is_file() => path,
+            _ => return Options::new()
+        }
+    };
+
+    match config::read_config_file(&path) {
+        Ok(options) => {
+            check_known_options(&format!("{}", path.display()), &options, K_CONFIG_FILE_OPTIONS, problems);
+            options
+        },
+        Err(e) => {
+            problems.push(e.message());
+            Options::new()
+        }
+    }
+}
+
+/// Combine all the sources of configuration into a single key/value
+/// structure according to the precedence described in 'K_USAGE'.
+fn resolve_settings(settings: &CryptoNixSettings, problems: &mut Problems) -> Options {
+
+    let args = parse_args(&settings.extra_args, problems);
+    let mut options = config_file_options(&args, true, problems);
+
+    options.extend(args);
+    options.extend(settings.typed_options());
+    options.remove(K_CONFIG_FILE);
+
+    options
+}
+
+/// Resolve options which have been supplied explicitly rather than
+/// via the Nix settings. Only the configuration file referenced by
+/// the 'config-file' option is consulted, if any, and the explicit
+/// options take precedence over it.
+fn resolve_explicit_options(source: &str, explicit: &[(String, String)], problems: &mut Problems) -> Options {
+
+    let explicit: Options = explicit.iter().cloned().collect();
+    check_known_options(source, &explicit, K_KNOWN_OPTIONS, problems);
+
+    let mut options = config_file_options(&explicit, false, problems);
+    options.extend(explicit);
+    options.remove(K_CONFIG_FILE);
+
+    options
+}
 
 /// Configuration representing the mode which uses
 /// the 'sled' crate to store credentials. This
@@ -49,26 +337,28 @@ pub struct SledModeConfig {
 
 impl SledModeConfig {
 
-    pub fn from_parsed_args(args: &HashMap<String, Vec<String>>) -> Result<Self, Error> {
-        let store_path =
-            &args.get(K_STORE_PATH)
-            .ok_or(
-                Error::from_message(
-                    format!("The CryptoNix '{}' mode requires the '{}' option, which must point to the location in the filesystem where CryptoNix will store the private credentials.", K_FILESYSTEM_MODE, K_STORE_PATH)
-                )
-            )?;
-
-        if store_path.len() == 0 {
-            return Error::fail_with(
-                format!("The option '{}' must not be empty.", K_STORE_PATH)
-            )
-        } else if store_path.len() > 1 {
-            return Error::fail_with(
-                format!("The option '{}' must only be used once.", K_STORE_PATH)
-            )
+    fn from_options(options: &Options, problems: &mut Problems) -> Option<Self> {
+
+        let Some(store_path) = options.get(K_STORE_PATH) else {
+            problems.push(
+                format!("The CryptoNix '{}' mode requires the '{}' option, which must point to the location in the filesystem where CryptoNix will store the private credentials.", K_FILESYSTEM_MODE, K_STORE_PATH)
+            );
+            return None;
+        };
+
+        if !check_absolute_path(K_STORE_PATH, store_path, problems) {
+            return None;
+        }
+
+        let path = Path::new(store_path);
+        if path.exists() && !path.is_dir() {
+            problems.push(
+                format!("The option '{}' must point to a directory, but '{}' is not a directory.", K_STORE_PATH, store_path)
+            );
+            return None;
         }
 
-        Ok(SledModeConfig { store_path: store_path[0].clone() })
+        Some(SledModeConfig { store_path: store_path.clone() })
     }
 }
 
@@ -88,49 +378,227 @@ pub enum CryptoNixMode {
 /// from the args supplied via the command line which get
 /// parsed using the 'parse_args' function.
 pub struct CryptoNixArgs {
-    pub mode : CryptoNixMode
+    pub mode : CryptoNixMode,
+    /// The number of pre-generated keys to keep for every
+    /// algorithm. A size of 0 disables the key pool.
+    pub key_pool_size : usize
 }
 
 impl CryptoNixArgs {
 
     fn from_error(error: Error) -> CryptoNixArgs {
-        CryptoNixArgs { mode: CryptoNixMode::ErrorMode(error) }
+        CryptoNixArgs { mode: CryptoNixMode::ErrorMode(error), key_pool_size: 0 }
     }
 
-    fn from_sled_mode(sled: SledModeConfig) -> CryptoNixArgs {
-        CryptoNixArgs { mode: CryptoNixMode::SledMode(sled) }
+    fn from_sled_mode(sled: SledModeConfig, key_pool_size: usize) -> CryptoNixArgs {
+        CryptoNixArgs { mode: CryptoNixMode::SledMode(sled), key_pool_size }
     }
 
-    fn from_args_with_error(query: &str) -> Result<CryptoNixArgs, Error> {
+    fn key_pool_size_from_options(options: &Options, problems: &mut Problems) -> usize {
 
-        let args = parse_args(query);
-        let mode = &args.get(K_MODE).ok_or(Error::from_message(format!("The '{}' option is not present in the CryptoNix parameters.\n{}", K_MODE, K_USAGE)))?;
+        let Some(value) = options.get(K_KEY_POOL_SIZE) else {
+            return 0;
+        };
 
-        if mode.len() == 0 {
-            return Error::fail_with(
-                format!("No mode provided to cryptonix. Please specify a mode to use CryptoNix via the 'option extra-cryptonix-args {}={}'", K_MODE, K_FILESYSTEM_MODE)
-            )
-        } else if mode.len() > 1 {
-            return Error::fail_with(
-                "Multiple modes have been provided to CryptoNix. Only a single mode must be supplied.".to_string()
-            )
+        match value.parse::<usize>() {
+            Ok(size) if size <= K_MAX_KEY_POOL_SIZE => size,
+            _ => {
+                problems.push(
+                    format!("The option '{}' must be a number between 0 and {}, found '{}'.", K_KEY_POOL_SIZE, K_MAX_KEY_POOL_SIZE, value)
+                );
+                0
+            }
         }
+    }
+
+    fn from_options(options: &Options, problems: &mut Problems) -> Option<CryptoNixArgs> {
+
+        let key_pool_size = Self::key_pool_size_from_options(options, problems);
 
-        match &mode[0][..] {
-            K_FILESYSTEM_MODE => Ok(
-               Self::from_sled_mode(SledModeConfig::from_parsed_args(&args)?)
-            ),
-            other => Error::fail_with(format!("The supplied mode '{}' is not a known CryptoNix operating mode. Plese consult the manual.", other))
+        let Some(mode) = options.get(K_MODE) else {
+            problems.push(
+                format!("No mode provided to CryptoNix. Please specify a mode, for example via '--option extra-cryptonix-args {}={}'.", K_MODE, K_FILESYSTEM_MODE)
+            );
+            return None;
+        };
+
+        match mode.as_str() {
+            K_FILESYSTEM_MODE =>
+                SledModeConfig::from_options(options, problems)
+                    .map(|sled| Self::from_sled_mode(sled, key_pool_size)),
+            other => {
+                problems.push(
+                    format!(
+                        "The supplied mode '{}' is not a known CryptoNix operating mode.{}",
+                        other,
+                        did_you_mean(other, K_KNOWN_MODES)
+                    )
+                );
+                None
+            }
         }
     }
 
-    pub fn from_args(query: &str) -> CryptoNixArgs {
+    fn from_resolved_options(options: Options, mut problems: Problems) -> Result<CryptoNixArgs, Error> {
+
+        let args = Self::from_options(&options, &mut problems);
+
+        problems.into_result(args)?.ok_or(
+            Error::internal("The configuration was rejected without reporting a problem.".to_string())
+        )
+    }
+
+    fn from_settings_with_error(settings: &CryptoNixSettings) -> Result<CryptoNixArgs, Error> {
+
+        let mut problems = Problems::default();
+        let options = resolve_settings(settings, &mut problems);
+        Self::from_resolved_options(options, problems)
+    }
+
+    /// Build the configuration from a set of options which have been
+    /// supplied explicitly, for example by calling 'builtins.crypto.open'
+    /// from Nix code. The 'source' describes where the options come
+    /// from and is used when reporting problems. Unlike 'from_settings',
+    /// this function neither consults the Nix settings nor the default
+    /// configuration file.
+    pub fn from_explicit_options(source: &str, options: &[(String, String)]) -> Result<CryptoNixArgs, Error> {
+
+        let mut problems = Problems::default();
+        let options = resolve_explicit_options(source, options, &mut problems);
+        Self::from_resolved_options(options, problems)
+    }
 
-        match Self::from_args_with_error(query) {
+    pub fn from_settings(settings: &CryptoNixSettings) -> CryptoNixArgs {
+
+        match Self::from_settings_with_error(settings) {
             Ok(args) => args,
             Err(e) => Self::from_error(e)
         }
     }
+
+    pub fn from_args(query: &str) -> CryptoNixArgs {
+        Self::from_settings(&CryptoNixSettings::from_args(query))
+    }
 }
 
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn parse(query: &str) -> (Options, Vec<String>) {
+        let mut problems = Problems::default();
+        let options = parse_args(query, &mut problems);
+        (options, problems.0)
+    }
+
+    fn error_message(args: CryptoNixArgs) -> String {
+        match args.mode {
+            CryptoNixMode::ErrorMode(e) => e.message(),
+            CryptoNixMode::SledMode(_) => panic!("expected the configuration to be rejected")
+        }
+    }
+
+    /// A configuration file with the given contents, removed on drop.
+    struct ConfigFile(PathBuf);
+
+    impl ConfigFile {
+        fn new(name: &str, contents: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("cryptonix-args-{}-{}.toml", std::process::id(), name));
+            fs::write(&path, contents).unwrap();
+            ConfigFile(path)
+        }
+    }
+
+    impl Drop for ConfigFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn percent_decodes_separators() {
+        let (options, problems) = parse("store-path=/tmp/a%26b%3Dc%25d&mode=filesystem");
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(options[K_STORE_PATH], "/tmp/a&b=c%d");
+        assert_eq!(options[K_MODE], "filesystem");
+    }
+
+    #[test]
+    fn percent_decode_rejects_invalid_sequences() {
+        assert_eq!(percent_decode("a+b%2fc").unwrap(), "a+b/c");
+        assert!(percent_decode("100%").is_err());
+        assert!(percent_decode("%zz").is_err());
+        assert!(percent_decode("%ff").is_err());
+    }
+
+    #[test]
+    fn suggests_close_options() {
+        assert_eq!(suggest("store_path", K_KNOWN_OPTIONS), Some(K_STORE_PATH));
+        assert_eq!(suggest("mdoe", K_KNOWN_OPTIONS), Some(K_MODE));
+        assert_eq!(suggest("unrelated", K_KNOWN_OPTIONS), None);
+        assert_eq!(did_you_mean("filesytem", K_KNOWN_MODES), " Did you mean 'filesystem'?");
+        assert_eq!(did_you_mean("memory", K_KNOWN_MODES), " Expected one of: filesystem.");
+    }
 
+    #[test]
+    fn rejects_duplicate_keys() {
+        let (options, problems) = parse("mode=filesystem&mode=other");
+        assert_eq!(options[K_MODE], "filesystem");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("'mode' was supplied more than once"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let (_, problems) = parse("mode=filesystem&store_path=/tmp/x");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("'store_path'"), "{}", problems[0]);
+        assert!(problems[0].contains("Did you mean 'store-path'?"), "{}", problems[0]);
+    }
+
+    #[test]
+    fn reports_all_problems_at_once() {
+        let message = error_message(CryptoNixArgs::from_args(
+            "mdoe=filesystem&store-path=relative&key-pool-size=1000&novalue&bad=%zz"
+        ));
+        for expected in [
+            "'novalue' in 'extra-cryptonix-args' is not formatted as 'key=value'",
+            "invalid percent-encoded sequence",
+            "'mdoe' supplied via 'extra-cryptonix-args' is not a known CryptoNix option. Did you mean 'mode'?",
+            "'key-pool-size' must be a number between 0 and 256, found '1000'",
+            "No mode provided to CryptoNix"
+        ] {
+            assert!(message.contains(expected), "missing '{expected}' in:\n{message}");
+        }
+    }
+
+    #[test]
+    fn reads_options_from_config_file() {
+        let file = ConfigFile::new("valid", "mode = \"filesystem\"\nstore-path = \"/tmp/cryptonix-store\"\nkey-pool-size = 4\n");
+        let args = CryptoNixArgs::from_explicit_options(
+            "test",
+            &[(K_CONFIG_FILE.to_string(), file.0.display().to_string())]
+        ).unwrap();
+
+        assert_eq!(args.key_pool_size, 4);
+        match args.mode {
+            CryptoNixMode::SledMode(sled) => assert_eq!(sled.store_path, "/tmp/cryptonix-store"),
+            CryptoNixMode::ErrorMode(e) => panic!("{}", e.message())
+        }
+    }
+
+    #[test]
+    fn rejects_config_file_inside_config_file() {
+        let file = ConfigFile::new("nested", "mode = \"filesystem\"\nstore-path = \"/tmp/cryptonix-store\"\nconfig-file = \"/etc/other.toml\"\n");
+        let message = CryptoNixArgs::from_explicit_options(
+            "test",
+            &[(K_CONFIG_FILE.to_string(), file.0.display().to_string())]
+        ).err().expect("expected the configuration to be rejected").message();
+
+        assert!(message.contains("The option 'config-file' supplied via"), "{message}");
+    }
+}
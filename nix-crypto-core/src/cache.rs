@@ -0,0 +1,62 @@
+use std::collections::{HashMap};
+use std::sync::{Mutex};
+
+use crate::error::*;
+use crate::openssl::pkey::{Key};
+
+/// A decoded key together with the value it was decoded from.
+struct CachedKey {
+    stored: Vec<u8>,
+    key: Key
+}
+
+/// An in-process cache of the private keys read from the store,
+/// indexed by the hash under which they are stored. Decoding a key
+/// and deriving its public encodings is far more expensive than
+/// reading the store, so the store is still read on every access
+/// and the cached key is only used if the stored value is unchanged.
+/// This keeps the cache correct even if a key is rotated or deleted.
+#[derive(Default)]
+pub struct KeyCache {
+    entries: Mutex<HashMap<Vec<u8>, CachedKey>>
+}
+
+impl KeyCache {
+
+    /// Obtain the key for the value currently held by the store under
+    /// 'store_key', decoding it with 'decode' if it is not cached.
+    pub fn get_or_decode<F>(
+        &self,
+        store_key: &[u8],
+        stored: Option<Vec<u8>>,
+        decode: F
+    ) -> Result<Option<Key>, Error>
+    where F: FnOnce(&Vec<u8>) -> Result<Key, Error> {
+
+        let Some(stored) = stored else {
+            self.invalidate(store_key);
+            return Ok(None);
+        };
+
+        let cached = self.lock().get(store_key)
+            .filter(|entry| entry.stored == stored)
+            .map(|entry| entry.key.clone());
+
+        if let Some(key) = cached {
+            return Ok(Some(key));
+        }
+
+        let key = decode(&stored)?;
+        self.lock().insert(store_key.to_vec(), CachedKey { stored, key: key.clone() });
+        Ok(Some(key))
+    }
+
+    /// Drop the key cached for 'store_key', if any.
+    pub fn invalidate(&self, store_key: &[u8]) {
+        self.lock().remove(store_key);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Vec<u8>, CachedKey>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
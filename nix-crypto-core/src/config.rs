@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error};
+
+const K_CONFIG_DIR : &str = "cryptonix";
+const K_CONFIG_FILE_NAME : &str = "config.toml";
+
+/// The location where 'CryptoNix' looks for a configuration
+/// file when none has been explicitly supplied. This follows
+/// the XDG base directory specification, meaning the file is
+/// "$XDG_CONFIG_HOME/cryptonix/config.toml" or, if that variable
+/// is not set, "$HOME/.config/cryptonix/config.toml".
+pub fn default_config_file() -> Option<PathBuf> {
+
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config")
+    };
+
+    Some(config_home.join(K_CONFIG_DIR).join(K_CONFIG_FILE_NAME))
+}
+
+/// Read a TOML configuration file and convert it into the same
+/// key/value structure produced when parsing the arguments
+/// supplied via "--option extra-cryptonix-args". The file is
+/// expected to consist of top-level keys only, for example:
+///
+/// ```toml
+/// mode = "filesystem"
+/// store-path = "/var/lib/cryptonix"
+/// ```
+pub fn read_config_file(path: &Path) -> Result<HashMap<String, String>, Error> {
+
+    let contents = fs::read_to_string(path).map_err(|e|
+        Error::configuration(
+            format!("Could not read the CryptoNix configuration file '{}': {}", path.display(), e)
+        )
+    )?;
+
+    let table = contents.parse::<toml::Table>().map_err(|e|
+        Error::configuration(
+            format!("The CryptoNix configuration file '{}' is not valid TOML: {}", path.display(), e)
+        )
+    )?;
+
+    let mut map: HashMap<String, String> = HashMap::new();
+
+    for (key, value) in table {
+        let value = match value {
+            toml::Value::String(s) => s,
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Boolean(b) => b.to_string(),
+            other => return Err(Error::configuration(
+                format!(
+                    "The option '{}' in the CryptoNix configuration file '{}' must be a string, an integer or a boolean, found a {}.",
+                    key,
+                    path.display(),
+                    other.type_str()
+                )
+            ))
+        };
+
+        map.insert(key, value);
+    }
+
+    Ok(map)
+}
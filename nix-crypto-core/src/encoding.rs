@@ -0,0 +1,117 @@
+use openssl::base64;
+
+use crate::error::{Error};
+
+/// Encode bytes using the standard base64 alphabet with
+/// padding (RFC 4648, section 4).
+pub fn to_base64(bytes: &[u8]) -> String {
+    base64::encode_block(bytes)
+}
+
+/// Encode bytes using the URL and filename safe base64 alphabet
+/// without padding (RFC 4648, section 5), as required by JOSE.
+pub fn to_base64url(bytes: &[u8]) -> String {
+    to_base64(bytes)
+        .trim_end_matches('=')
+        .replace('+', "-")
+        .replace('/', "_")
+}
+
+/// Encode bytes as lowercase hexadecimal digits.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decode base64 using the standard alphabet (RFC 4648, section 4).
+/// Whitespace, such as the line breaks of PEM bodies, is ignored.
+pub fn from_base64(text: &str) -> Result<Vec<u8>, Error> {
+
+    let text: String = text.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    base64::decode_block(&text).map_err(|_| Error::invalid_argument(
+        "The value is not valid base64.".to_string()
+    ))
+}
+
+/// Decode base64 using the URL and filename safe alphabet
+/// (RFC 4648, section 5), with or without padding.
+pub fn from_base64url(text: &str) -> Result<Vec<u8>, Error> {
+
+    let mut standard = text.trim_end_matches('=').replace('-', "+").replace('_', "/");
+    while !standard.len().is_multiple_of(4) {
+        standard.push('=');
+    }
+
+    base64::decode_block(&standard).map_err(|_| Error::invalid_argument(
+        "The value is not valid base64url.".to_string()
+    ))
+}
+
+/// Decode hexadecimal digits, in either case.
+pub fn from_hex(text: &str) -> Result<Vec<u8>, Error> {
+
+    let error = || Error::invalid_argument("The value is not a valid hexadecimal string.".to_string());
+
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return Err(error());
+    }
+
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).map_err(|_| error()))
+        .collect()
+}
+
+/// The text encodings in which binary values, such as
+/// signatures, can be exchanged with Nix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    #[default]
+    Base64,
+    Base64Url,
+    Hex
+}
+
+/// The names used to refer to each encoding from Nix.
+const K_TEXT_ENCODINGS : &[(&str, TextEncoding)] = &[
+    ("base64", TextEncoding::Base64),
+    ("base64url", TextEncoding::Base64Url),
+    ("hex", TextEncoding::Hex)
+];
+
+impl TextEncoding {
+
+    pub fn encode(&self, bytes: &[u8]) -> String {
+
+        match self {
+            TextEncoding::Base64 => to_base64(bytes),
+            TextEncoding::Base64Url => to_base64url(bytes),
+            TextEncoding::Hex => to_hex(bytes)
+        }
+    }
+
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>, Error> {
+
+        match self {
+            TextEncoding::Base64 => from_base64(text),
+            TextEncoding::Base64Url => from_base64url(text),
+            TextEncoding::Hex => from_hex(text)
+        }
+    }
+}
+
+impl TryFrom<&str> for TextEncoding {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<TextEncoding, Error> {
+
+        K_TEXT_ENCODINGS.iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, encoding)| *encoding)
+            .ok_or_else(|| {
+                let names: Vec<&str> = K_TEXT_ENCODINGS.iter().map(|(name, _)| *name).collect();
+                Error::invalid_argument(format!(
+                    "The value '{value}' is not a known encoding. The supported encodings are: {}.",
+                    names.join(", ")
+                ))
+            })
+    }
+}
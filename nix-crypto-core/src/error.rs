@@ -3,6 +3,56 @@ use openssl::error::{ErrorStack};
 use std::fmt;
 use std::string;
 
+/// Stable codes which identify the kind of an 'Error'. The codes
+/// are part of the interface of 'CryptoNix' as they are exposed
+/// to Nix code, so existing codes must never be renamed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// An operation performed by the OpenSSL library failed.
+    Openssl,
+    /// A value could not be exchanged between C++ and Rust.
+    Ffi,
+    /// A value was expected to be a valid UTF-8 string.
+    Utf8,
+    /// The store holding the private credentials failed.
+    Store,
+    /// CryptoNix has not been configured or the configuration
+    /// is invalid.
+    Configuration,
+    /// A parameter supplied to CryptoNix is invalid.
+    InvalidArgument,
+    /// A date could not be parsed.
+    InvalidDate,
+    /// A bug in CryptoNix.
+    Internal,
+    /// An error that does not fit in any of the other categories.
+    CryptoNix
+}
+
+impl ErrorCode {
+
+    pub fn as_str(&self) -> &'static str {
+
+        match self {
+            ErrorCode::Openssl => "openssl",
+            ErrorCode::Ffi => "ffi",
+            ErrorCode::Utf8 => "utf8",
+            ErrorCode::Store => "store",
+            ErrorCode::Configuration => "configuration",
+            ErrorCode::InvalidArgument => "invalid-argument",
+            ErrorCode::InvalidDate => "invalid-date",
+            ErrorCode::Internal => "internal",
+            ErrorCode::CryptoNix => "cryptonix"
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 /// The type used to represent errors that occur
 /// within the 'CryptoNix' crate. All functions
 /// that can fail will fail with this error type.
@@ -15,7 +65,13 @@ pub enum Error {
     FromUtf8Error(string::FromUtf8Error),
     SledError(sled::Error),
     CryptoNixError(String),
-    TimeParseError(time::error::Parse)
+    TimeParseError(time::error::Parse),
+    ConfigurationError(String),
+    InvalidArgumentError(String),
+    InternalError(String),
+    /// An error together with a description of the operation
+    /// that was being performed when the error occurred.
+    ContextError(String, Box<Error>)
 }
 
 impl From<time::error::Parse> for Error {
@@ -48,17 +104,12 @@ impl From<string::FromUtf8Error> for Error {
     }
 }
 
+/// Errors are displayed as "[code] message" so the code
+/// remains available to consumers which only receive the
+/// textual representation of the error (such as C++).
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-
-        match self {
-            Error::OpensslError(stack) => stack.fmt(f),
-            Error::CxxError(msg) => write!(f, "{}", msg),
-            Error::Utf8Error(msg) => msg.fmt(f),
-            Error::FromUtf8Error(msg) => msg.fmt(f),
-            Error::CryptoNixError(msg) => msg.fmt(f),
-            _ => write!(f, "Unknown error in the 'nix-crypto' Rust code.")
-        }
+        write!(f, "[{}] {}", self.code(), self.message())
     }
 }
 
@@ -71,4 +122,89 @@ impl Error {
     pub fn from_message(msg: String) -> Error {
         Error::CryptoNixError(msg)
     }
+
+    pub fn configuration(msg: String) -> Error {
+        Error::ConfigurationError(msg)
+    }
+
+    pub fn invalid_argument(msg: String) -> Error {
+        Error::InvalidArgumentError(msg)
+    }
+
+    pub fn internal(msg: String) -> Error {
+        Error::InternalError(msg)
+    }
+
+    /// Attach a description of the operation that was being
+    /// performed when this error occurred. For example,
+    /// "while signing certificate serial 7 for identity 'vault=prod'".
+    pub fn context(self, context: String) -> Error {
+        Error::ContextError(context, Box::new(self))
+    }
+
+    /// The stable code of this error. Errors with context
+    /// have the code of the error which caused them.
+    pub fn code(&self) -> ErrorCode {
+
+        match self {
+            Error::OpensslError(_) => ErrorCode::Openssl,
+            Error::CxxError(_) => ErrorCode::Ffi,
+            Error::Utf8Error(_) => ErrorCode::Utf8,
+            Error::FromUtf8Error(_) => ErrorCode::Utf8,
+            Error::SledError(_) => ErrorCode::Store,
+            Error::CryptoNixError(_) => ErrorCode::CryptoNix,
+            Error::TimeParseError(_) => ErrorCode::InvalidDate,
+            Error::ConfigurationError(_) => ErrorCode::Configuration,
+            Error::InvalidArgumentError(_) => ErrorCode::InvalidArgument,
+            Error::InternalError(_) => ErrorCode::Internal,
+            Error::ContextError(_, source) => source.code()
+        }
+    }
+
+    /// The human readable description of this error, including
+    /// its context, but not its code.
+    pub fn message(&self) -> String {
+
+        match self {
+            Error::OpensslError(stack) =>
+                format!("The OpenSSL library reported an error: {}", stack),
+            Error::CxxError(msg) =>
+                format!("Invalid value received from the Nix plugin: {}", msg),
+            Error::Utf8Error(e) =>
+                format!("Expected a valid UTF-8 string: {}", e),
+            Error::FromUtf8Error(e) =>
+                format!("Expected a valid UTF-8 string: {}", e),
+            Error::SledError(e) =>
+                format!("The CryptoNix store could not be accessed: {}", e),
+            Error::TimeParseError(e) =>
+                format!("Invalid date: {}. Dates must be formatted according to RFC 3339, for example '2026-01-09T21:29:36Z'.", e),
+            Error::InternalError(msg) =>
+                format!("Bug in CryptoNix. {} Please report this issue.", msg),
+            Error::CryptoNixError(msg)
+            | Error::ConfigurationError(msg)
+            | Error::InvalidArgumentError(msg) => msg.clone(),
+            Error::ContextError(context, source) =>
+                format!("{}: {}", context, source.message())
+        }
+    }
+}
+
+/// Extension trait to attach context to the errors of a
+/// 'Result'. The context should describe the operation
+/// that was being performed, for example
+/// "while reading the 'start-date' parameter".
+pub trait ErrorContext<T> {
+    fn context(self, context: &str) -> Result<T, Error>;
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> ErrorContext<T> for Result<T, E> {
+
+    fn context(self, context: &str) -> Result<T, Error> {
+        self.map_err(|e| e.into().context(context.to_string()))
+    }
+
+    fn with_context<F: FnOnce() -> String>(self, context: F) -> Result<T, Error> {
+        self.map_err(|e| e.into().context(context()))
+    }
 }
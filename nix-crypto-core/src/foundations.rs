@@ -1,11 +1,17 @@
 use std::borrow::{Borrow};
+use std::sync::{Arc};
 
-use crate::args::{CryptoNixArgs, CryptoNixMode, SledModeConfig};
+use crate::args::{CryptoNixArgs, CryptoNixMode, CryptoNixSettings, SledModeConfig};
+use crate::cache::{KeyCache};
 use crate::error::*;
+use crate::openssl::pkey::{Algorithm, Key};
+use crate::pool::{KeyPool};
 use crate::store::*;
 
 pub struct CryptoNix {
-    store : Box<dyn CryptoStore>
+    store : Arc<dyn CryptoStore + Send + Sync>,
+    key_pool : Option<Arc<KeyPool>>,
+    key_cache : KeyCache
 }
 
 impl CryptoNix {
@@ -31,6 +37,20 @@ impl CryptoNix {
         }
     }
 
+    /// Like 'get', but for private keys. Decoded keys, and the public
+    /// material derived from them, are cached in memory for as long as
+    /// the store holds the same value for them.
+    pub fn get_key<K: IsCryptoStoreKey<Value = Key>>(
+        &self,
+        key: &K
+    ) -> Result<Option<Key>, Error> {
+
+        let store_key = self.to_store_key_raw(key);
+        let stored = self.store.get_raw(&store_key[..])?;
+
+        self.key_cache.get_or_decode(&store_key, stored, <K as IsCryptoStoreKey>::from_store_value_raw)
+    }
+
     pub fn put<K: IsCryptoStoreKey>(
         &self,
         key: &K,
@@ -38,20 +58,97 @@ impl CryptoNix {
     ) -> Result<(), Error>
     where {
 
+        let store_key = self.to_store_key_raw(key);
+        self.key_cache.invalidate(&store_key);
+
         self.store.put_raw(
-            &self.to_store_key_raw(key)[..],
+            &store_key[..],
             <K as IsCryptoStoreKey>::to_store_value_raw(value)?
         )
     }
 
+    /// Get the value associated with 'key', or save the value built by
+    /// 'generate' if there is none. Every value that is returned, be it
+    /// read or generated by another thread, passes through 'check' first.
+    /// The value is described by 'describe', e.g. "the password of ...".
+    pub fn get_or_insert_with<K: IsCryptoStoreKey>(
+        &self,
+        key: &K,
+        describe: impl Fn() -> String,
+        generate: impl FnOnce() -> Result<<K as IsCryptoStoreKey>::Value, Error>,
+        check: impl Fn(<K as IsCryptoStoreKey>::Value) -> Result<<K as IsCryptoStoreKey>::Value, Error>
+    ) -> Result<<K as IsCryptoStoreKey>::Value, Error> {
+
+        self.get_or_insert_by(key, Self::get, describe, generate, check)
+    }
+
+    /// Like 'get_or_insert_with', but for private keys, which are read
+    /// through the cache of 'get_key'.
+    pub fn get_or_insert_key_with<K: IsCryptoStoreKey<Value = Key>>(
+        &self,
+        key: &K,
+        describe: impl Fn() -> String,
+        generate: impl FnOnce() -> Result<Key, Error>,
+        check: impl Fn(Key) -> Result<Key, Error>
+    ) -> Result<Key, Error> {
+
+        self.get_or_insert_by(key, Self::get_key, describe, generate, check)
+    }
+
+    fn get_or_insert_by<K: IsCryptoStoreKey>(
+        &self,
+        key: &K,
+        read: impl Fn(&Self, &K) -> Result<Option<<K as IsCryptoStoreKey>::Value>, Error>,
+        describe: impl Fn() -> String,
+        generate: impl FnOnce() -> Result<<K as IsCryptoStoreKey>::Value, Error>,
+        check: impl Fn(<K as IsCryptoStoreKey>::Value) -> Result<<K as IsCryptoStoreKey>::Value, Error>
+    ) -> Result<<K as IsCryptoStoreKey>::Value, Error> {
+
+        if let Some(value) = read(self, key).with_context(|| format!("while reading {}", describe()))? {
+            return check(value);
+        }
+
+        let value = generate()?;
+
+        if let Err(e) = self.put(key, &value) {
+            // Another thread may have saved the key first, see 'CryptoStore::put_raw'.
+            return match read(self, key) {
+                Ok(Some(existing)) => check(existing),
+                _ => Err(e.context(format!("while saving {}", describe())))
+            };
+        }
+        Ok(value)
+    }
+
     pub fn salt(&self) -> Vec<u8> {
         self.store.salt()
     }
 
-    fn from_sled_config(config: &SledModeConfig) -> CryptoNix {
+    /// Claim a pre-generated key of the given algorithm. This
+    /// yields 'None' if the key pool is disabled or empty.
+    pub fn claim_pooled_key(&self, algorithm: &Algorithm) -> Result<Option<Key>, Error> {
+
+        match &self.key_pool {
+            Some(pool) => pool.claim(algorithm),
+            None => Ok(None)
+        }
+    }
+
+    fn from_store(store: Arc<dyn CryptoStore + Send + Sync>, key_pool_size: usize) -> CryptoNix {
+
+        let key_pool = if key_pool_size > 0 {
+            Some(KeyPool::new(key_pool_size, Arc::clone(&store)))
+        } else {
+            None
+        };
+
+        CryptoNix { store, key_pool, key_cache: KeyCache::default() }
+    }
+
+    fn from_sled_config(config: &SledModeConfig, key_pool_size: usize) -> CryptoNix {
 
         match SledStore::open(&config.store_path) {
-            Ok(store) => CryptoNix { store: Box::new(store) },
+            Ok(store) => Self::from_store(Arc::new(store), key_pool_size),
             Err(err) => Self::with_error(err)
         }
     }
@@ -59,7 +156,7 @@ impl CryptoNix {
     fn from_parsed_args(args: CryptoNixArgs) -> CryptoNix {
 
         match args.mode {
-            CryptoNixMode::SledMode(sled) => Self::from_sled_config(&sled),
+            CryptoNixMode::SledMode(sled) => Self::from_sled_config(&sled, args.key_pool_size),
             CryptoNixMode::ErrorMode(err) => Self::with_error(err)
         }
     }
@@ -74,9 +171,33 @@ impl CryptoNix {
         Self::from_parsed_args(CryptoNixArgs::from_args(args))
     }
 
+    /// Build a CryptoNix instance from all the settings supplied
+    /// by Nix. This behaves like 'with_args', but also takes the
+    /// typed Nix settings and the configuration file into account.
+    pub fn with_settings(settings: &CryptoNixSettings) -> CryptoNix {
+        Self::from_parsed_args(CryptoNixArgs::from_settings(settings))
+    }
+
+    /// Build a CryptoNix instance from options supplied explicitly,
+    /// such as the ones given to 'builtins.crypto.open'. As opposed to
+    /// 'with_settings', this function fails if the options are invalid
+    /// or the store cannot be opened, since the caller explicitly
+    /// requested the instance.
+    pub fn open(source: &str, options: &[(String, String)]) -> Result<CryptoNix, Error> {
+
+        let args = CryptoNixArgs::from_explicit_options(source, options)?;
+        match args.mode {
+            CryptoNixMode::SledMode(sled) =>
+                Ok(Self::from_store(Arc::new(SledStore::open(&sled.store_path)?), args.key_pool_size)),
+            CryptoNixMode::ErrorMode(err) => Err(err)
+        }
+    }
+
     pub fn with_error(error: Error) -> CryptoNix {
         CryptoNix{
-            store : Box::new(ErrorStore::from_error(error))
+            store : Arc::new(ErrorStore::from_error(error)),
+            key_pool : None,
+            key_cache : KeyCache::default()
         }
     }
 }
@@ -0,0 +1,39 @@
+use openssl::md::{MdRef};
+use openssl::pkey::{Id};
+use openssl::pkey_ctx::{HkdfMode, PkeyCtx};
+
+use crate::error::*;
+
+/// The 'HKDF-Extract' step of HKDF (RFC 5869, section 2.2). An
+/// empty 'salt' is equivalent to a salt of zeros, as required.
+pub fn extract(digest: &MdRef, salt: &[u8], ikm: &[u8]) -> Result<Vec<u8>, Error> {
+
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(digest)?;
+    ctx.set_hkdf_mode(HkdfMode::EXTRACT_ONLY)?;
+    ctx.set_hkdf_key(ikm)?;
+
+    if !salt.is_empty() {
+        ctx.set_hkdf_salt(salt)?;
+    }
+
+    let mut prk = Vec::new();
+    ctx.derive_to_vec(&mut prk)?;
+    Ok(prk)
+}
+
+/// The 'HKDF-Expand' step of HKDF (RFC 5869, section 2.3).
+pub fn expand(digest: &MdRef, prk: &[u8], info: &[u8], length: usize) -> Result<Vec<u8>, Error> {
+
+    let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+    ctx.derive_init()?;
+    ctx.set_hkdf_md(digest)?;
+    ctx.set_hkdf_mode(HkdfMode::EXPAND_ONLY)?;
+    ctx.set_hkdf_key(prk)?;
+    ctx.add_hkdf_info(info)?;
+
+    let mut okm = vec![0; length];
+    ctx.derive(Some(&mut okm))?;
+    Ok(okm)
+}
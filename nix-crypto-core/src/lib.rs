@@ -1,6 +1,19 @@
 pub mod error;
 pub mod args;
+pub mod cache;
+pub mod config;
+pub mod encoding;
 pub mod foundations;
+pub mod hkdf;
+pub mod jose;
+pub mod minisign;
+pub mod nix_cache;
 pub mod age;
 pub mod openssl;
+pub mod openpgp;
+pub mod password;
+pub mod pool;
 pub mod store;
+pub mod symmetric;
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,173 @@
+use openssl::hash::{MessageDigest, hash};
+
+use crate::encoding;
+use crate::error::{Error, ErrorContext};
+use crate::foundations::{CryptoNix};
+use crate::openssl::{ReservedKeyIdentity};
+use crate::openssl::ffi::{IsOpensslPrivateKeyIdentity};
+use crate::openssl::pkey::{PublicKeyEncoding};
+use crate::openssl::signature::{SignatureAlgorithm};
+
+// The kind of the minisign keys, see 'ReservedKeyIdentity'
+const K_KEY_KIND : &str = "minisign";
+
+// The limits of minisign on the length of the comments
+const K_MAX_UNTRUSTED_COMMENT_BYTES : usize = 1024;
+const K_MAX_TRUSTED_COMMENT_BYTES : usize = 8192;
+
+const K_DEFAULT_UNTRUSTED_COMMENT : &str = "signature from minisign secret key";
+
+/// The comments of a minisign signature. The untrusted comment is
+/// not signed, whereas the trusted comment is signed together with
+/// the signature, and is shown by 'minisign -V'.
+#[derive(Default)]
+pub struct MinisignComments {
+    /// Defaults to the comment used by minisign itself.
+    pub untrusted: Option<String>,
+    /// Defaults to the empty comment. Minisign puts a timestamp
+    /// in this comment, which would change every signature.
+    pub trusted: Option<String>
+}
+
+fn check_comment(comment: &str, kind: &str, max_bytes: usize) -> Result<(), Error> {
+
+    if comment.contains(['\n', '\r']) {
+        return Err(Error::invalid_argument(format!(
+            "The {kind} comment of a minisign signature must be a single line."
+        )));
+    }
+
+    if comment.len() > max_bytes {
+        return Err(Error::invalid_argument(format!(
+            "The {kind} comment of a minisign signature must have at most {max_bytes} bytes, found {}.",
+            comment.len()
+        )));
+    }
+
+    Ok(())
+}
+
+impl CryptoNix {
+
+    /// The public key of the minisign key of the given identity, in
+    /// the 'minisign' encoding, as passed to 'minisign -V -P', or in
+    /// the 'signify' encoding, as the contents of a public key file.
+    pub fn minisign_public_key<T : IsOpensslPrivateKeyIdentity>(
+        &self,
+        key_identity: &T,
+        encoding: PublicKeyEncoding
+    ) -> Result<String, Error> {
+
+        if !matches!(encoding, PublicKeyEncoding::Minisign | PublicKeyEncoding::Signify) {
+            return Err(Error::invalid_argument(
+                "The public key of a minisign key is encoded as 'minisign' or 'signify'.".to_string()
+            ));
+        }
+
+        let key = self.openssl_private_key(&ReservedKeyIdentity::new(K_KEY_KIND, key_identity))?;
+        key.public_key_encoded(encoding)
+            .with_context(|| format!("while reading the minisign key '{}'", key_identity.key_id()))
+    }
+
+    /// A minisign signature file over 'data', made with the minisign
+    /// key of the given identity. The data is prehashed with BLAKE2b,
+    /// as done by default since minisign 0.8. The signature is checked
+    /// with 'minisign -V -P <public key>', the public key being the one
+    /// given by 'minisign_public_key'. Ed25519 is deterministic, so
+    /// signing the same data again gives the same signature.
+    pub fn minisign_sign<T : IsOpensslPrivateKeyIdentity>(
+        &self,
+        key_identity: &T,
+        data: &[u8],
+        comments: &MinisignComments
+    ) -> Result<String, Error> {
+
+        let untrusted = comments.untrusted.as_deref().unwrap_or(K_DEFAULT_UNTRUSTED_COMMENT);
+        let trusted = comments.trusted.as_deref().unwrap_or_default();
+        check_comment(untrusted, "untrusted", K_MAX_UNTRUSTED_COMMENT_BYTES)?;
+        check_comment(trusted, "trusted", K_MAX_TRUSTED_COMMENT_BYTES)?;
+
+        let key_identity = &ReservedKeyIdentity::new(K_KEY_KIND, key_identity);
+        let key = self.openssl_private_key(key_identity)?;
+        let public_blob = key.public_minisign_blob()
+            .with_context(|| format!("while reading the minisign key '{}'", key_identity.key_id()))?;
+        let key_number = &public_blob[2..10];
+
+        let blake2b = MessageDigest::from_name("BLAKE2B512")
+            .ok_or_else(|| Error::configuration("The BLAKE2b-512 digest is not available in OpenSSL.".to_string()))?;
+        let prehashed = hash(blake2b, data)?;
+
+        let signature = self.openssl_sign(key_identity, Some(SignatureAlgorithm::Ed25519), &prehashed)
+            .context("while signing the data with the minisign key")?;
+        let global_signature = self.openssl_sign(
+            key_identity,
+            Some(SignatureAlgorithm::Ed25519),
+            &[&signature[..], trusted.as_bytes()].concat()
+        ).context("while signing the trusted comment with the minisign key")?;
+
+        Ok(format!(
+            "untrusted comment: {untrusted}\n{}\ntrusted comment: {trusted}\n{}\n",
+            encoding::to_base64(&[b"ED", key_number, &signature[..]].concat()),
+            encoding::to_base64(&global_signature)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openssl::pkey::{Id, PKey};
+    use openssl::sign::{Verifier};
+
+    use super::*;
+    use crate::testing::{TempStore, assert_reserves_the_key, identity};
+
+    fn verify(public_key: &[u8], data: &[u8], signature: &[u8]) -> bool {
+        let public_key = PKey::public_key_from_raw_bytes(public_key, Id::ED25519).unwrap();
+        Verifier::new_without_digest(&public_key).unwrap().verify_oneshot(signature, data).unwrap()
+    }
+
+    #[test]
+    fn signatures_are_checked_by_the_public_key() {
+        let store = TempStore::new();
+        let crypto_nix = store.open();
+        let identity = identity("ed25519", "name=update-channel");
+        let comments = MinisignComments {
+            untrusted: None,
+            trusted: Some("file:channel.tar.xz".to_string())
+        };
+
+        let public_key = crypto_nix.minisign_public_key(&identity, PublicKeyEncoding::Minisign).unwrap();
+        let public_blob = encoding::from_base64(&public_key).unwrap();
+        let signature = crypto_nix.minisign_sign(&identity, b"channel", &comments).unwrap();
+        let lines: Vec<&str> = signature.lines().collect();
+        assert_eq!(lines[0], "untrusted comment: signature from minisign secret key");
+        assert_eq!(lines[2], "trusted comment: file:channel.tar.xz");
+
+        let signature_blob = encoding::from_base64(lines[1]).unwrap();
+        assert_eq!(&signature_blob[..2], b"ED");
+        assert_eq!(signature_blob[2..10], public_blob[2..10]);
+
+        let prehashed = hash(MessageDigest::from_name("BLAKE2B512").unwrap(), b"channel").unwrap();
+        let global_signature = encoding::from_base64(lines[3]).unwrap();
+        assert!(verify(&public_blob[10..], &prehashed, &signature_blob[10..]));
+        assert!(!verify(&public_blob[10..], b"tampered", &signature_blob[10..]));
+        assert!(verify(&public_blob[10..], &[&signature_blob[10..], b"file:channel.tar.xz"].concat(), &global_signature));
+
+        assert_eq!(
+            crypto_nix.minisign_public_key(&identity, PublicKeyEncoding::Signify).unwrap(),
+            format!("untrusted comment: signify public key\n{public_key}\n")
+        );
+        assert!(crypto_nix.minisign_public_key(&identity, PublicKeyEncoding::Pem).is_err());
+    }
+
+    #[test]
+    fn reserves_the_keys_to_minisign() {
+        let identity = identity("ed25519", "name=update-channel");
+        assert_reserves_the_key(
+            &identity,
+            |crypto_nix| crypto_nix.minisign_public_key(&identity, PublicKeyEncoding::Minisign).unwrap(),
+            |key| key.public_key_encoded(PublicKeyEncoding::Minisign).unwrap()
+        );
+    }
+}
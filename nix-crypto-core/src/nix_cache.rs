@@ -0,0 +1,163 @@
+use crate::encoding;
+use crate::error::{Error, ErrorContext};
+use crate::foundations::{CryptoNix};
+use crate::openssl::{ReservedKeyIdentity};
+use crate::openssl::ffi::{IsOpensslPrivateKeyIdentity};
+use crate::openssl::pkey::{Key, Type};
+use crate::openssl::signature::{SignatureAlgorithm};
+
+// The kind of the Nix binary cache keys, see 'ReservedKeyIdentity'
+const K_KEY_KIND : &str = "nix-cache";
+
+/// Check the name of a binary cache key, such as 'cache.example.org-1',
+/// which prefixes the keys and the signatures.
+fn check_key_name(name: &str) -> Result<(), Error> {
+
+    if name.is_empty() || name.contains(':') || name.contains(char::is_whitespace) {
+        return Err(Error::invalid_argument(format!(
+            "The name of a Nix binary cache key must be non-empty, without colons or whitespace, found '{name}'."
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check that 'fingerprint' has the form of the fingerprint of a store
+/// path in a narinfo: '1;<store path>;sha256:<hash>;<nar size>;<references>',
+/// the references being store paths separated by commas.
+fn check_fingerprint(fingerprint: &str) -> Result<(), Error> {
+
+    let fields: Vec<&str> = fingerprint.split(';').collect();
+    let valid = match fields[..] {
+        ["1", store_path, nar_hash, nar_size, references] =>
+            store_path.starts_with('/')
+            && nar_hash.strip_prefix("sha256:").is_some_and(|hash| !hash.is_empty())
+            && !nar_size.is_empty()
+            && nar_size.bytes().all(|b| b.is_ascii_digit())
+            && (references.is_empty() || references.split(',').all(|reference| reference.starts_with('/'))),
+        _ => false
+    };
+
+    if !valid {
+        return Err(Error::invalid_argument(format!(
+            "The value '{fingerprint}' is not the fingerprint of a store path, which has the form '1;<store path>;sha256:<hash>;<nar size>;<references>'."
+        )));
+    }
+
+    Ok(())
+}
+
+impl CryptoNix {
+
+    /// The key of the given identity reserved to the binary cache
+    /// keys, which is not the 'ed25519' key of the same identity.
+    fn nix_cache_key<T : IsOpensslPrivateKeyIdentity>(&self, key_identity: &T, name: &str) -> Result<Key, Error> {
+
+        check_key_name(name)?;
+
+        let key = self.openssl_private_key(&ReservedKeyIdentity::new(K_KEY_KIND, key_identity))?;
+        let key_type = key.key_type()?;
+        if key_type != Type::Ed25519Key {
+            return Err(Error::invalid_argument(format!(
+                "Nix binary cache keys must be 'ed25519' keys, found a key of type '{}' for the identity '{}'.",
+                key_type.name(),
+                key_identity.key_id()
+            )));
+        }
+
+        Ok(key)
+    }
+
+    /// The public key as listed in the 'trusted-public-keys'
+    /// setting of Nix, i.e. '<name>:<base64 public key>'.
+    pub fn nix_cache_public_key<T : IsOpensslPrivateKeyIdentity>(&self, key_identity: &T, name: &str) -> Result<String, Error> {
+
+        let key = self.nix_cache_key(key_identity, name)?;
+        Ok(format!("{name}:{}", encoding::to_base64(&key.public_raw()?)))
+    }
+
+    /// The secret key in the format of the files listed in the
+    /// 'secret-key-files' setting of Nix, as written by 'nix key
+    /// generate-secret'. This must never be exposed to Nix, which
+    /// would copy it to the Nix store.
+    pub fn nix_cache_secret_key<T : IsOpensslPrivateKeyIdentity>(&self, key_identity: &T, name: &str) -> Result<String, Error> {
+
+        let key = self.nix_cache_key(key_identity, name)?;
+        let seed = key.pkey.raw_private_key()?;
+        Ok(format!("{name}:{}", encoding::to_base64(&[seed, key.public_raw()?].concat())))
+    }
+
+    /// Sign the 'fingerprint' of a store path, as computed by Nix from
+    /// the fields of its narinfo. The result is a 'Sig' of the narinfo,
+    /// i.e. '<name>:<base64 signature>'.
+    pub fn nix_cache_sign<T : IsOpensslPrivateKeyIdentity>(
+        &self,
+        key_identity: &T,
+        name: &str,
+        fingerprint: &str
+    ) -> Result<String, Error> {
+
+        check_fingerprint(fingerprint)?;
+        self.nix_cache_key(key_identity, name)?;
+
+        let key_identity = &ReservedKeyIdentity::new(K_KEY_KIND, key_identity);
+        let signature = self.openssl_sign(key_identity, Some(SignatureAlgorithm::Ed25519), fingerprint.as_bytes())
+            .with_context(|| format!("while signing the fingerprint '{fingerprint}'"))?;
+        Ok(format!("{name}:{}", encoding::to_base64(&signature)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openssl::pkey::{Id, PKey};
+    use openssl::sign::{Verifier};
+
+    use super::*;
+    use crate::openssl::pkey::{PublicKeyEncoding};
+    use crate::testing::{TempStore, assert_reserves_the_key, identity};
+
+    const K_FINGERPRINT : &str = "1;/nix/store/02bfycjg1607gpcnsg8l13lc45qa8qj3-hello-2.12.1;sha256:1l5ljyjk2jbbb5ahg01i1xg9fhqqm6gkf3jjfsb2x4d5vrirqsg8;226560;";
+
+    #[test]
+    fn signatures_are_checked_by_the_public_key() {
+        let store = TempStore::new();
+        let crypto_nix = store.open();
+        let identity = identity("ed25519", "name=binary-cache");
+
+        let public_key = crypto_nix.nix_cache_public_key(&identity, "cache.example.org-1").unwrap();
+        let secret_key = crypto_nix.nix_cache_secret_key(&identity, "cache.example.org-1").unwrap();
+        let signature = crypto_nix.nix_cache_sign(&identity, "cache.example.org-1", K_FINGERPRINT).unwrap();
+
+        let public_raw = encoding::from_base64(public_key.strip_prefix("cache.example.org-1:").unwrap()).unwrap();
+        let secret_raw = encoding::from_base64(secret_key.strip_prefix("cache.example.org-1:").unwrap()).unwrap();
+        let signature = encoding::from_base64(signature.strip_prefix("cache.example.org-1:").unwrap()).unwrap();
+        assert_eq!(secret_raw.len(), 64);
+        assert_eq!(secret_raw[32..], public_raw[..]);
+        assert_eq!(
+            PKey::private_key_from_raw_bytes(&secret_raw[..32], Id::ED25519).unwrap().raw_public_key().unwrap(),
+            public_raw
+        );
+
+        let public_key = PKey::public_key_from_raw_bytes(&public_raw, Id::ED25519).unwrap();
+        let verify = |data: &[u8]| Verifier::new_without_digest(&public_key).unwrap().verify_oneshot(&signature, data).unwrap();
+        assert!(verify(K_FINGERPRINT.as_bytes()));
+        assert!(!verify(b"1;/nix/store/tampered"));
+
+        // The name is not part of the identity
+        assert_eq!(
+            crypto_nix.nix_cache_public_key(&identity, "cache.example.org-2").unwrap(),
+            format!("cache.example.org-2:{}", encoding::to_base64(&public_raw))
+        );
+    }
+
+    #[test]
+    fn reserves_the_keys_to_the_binary_cache() {
+        let identity = identity("ed25519", "name=binary-cache");
+        assert_reserves_the_key(
+            &identity,
+            |crypto_nix| crypto_nix.nix_cache_public_key(&identity, "cache.example.org-1").unwrap(),
+            |key| format!("cache.example.org-1:{}", key.public_key_encoded(PublicKeyEncoding::RawBase64).unwrap())
+        );
+    }
+}
@@ -0,0 +1,656 @@
+use openssl::ecdsa::{EcdsaSig};
+use openssl::hash::{MessageDigest, hash};
+use openssl::sha::{sha1};
+
+use crate::encoding;
+use crate::error::{Error, ErrorContext};
+use crate::foundations::{CryptoNix};
+use crate::openssl::{ReservedKeyIdentity};
+use crate::openssl::ffi::{IsOpensslPrivateKeyIdentity, unix_time_rfc3339};
+use crate::openssl::pkey::{Key, Type};
+use crate::openssl::signature::{SignatureAlgorithm};
+
+/// See the 'ffi' module of 'crate::openssl' for the reason why the
+/// parameters are described by traits.
+pub mod ffi {
+
+    use crate::error::*;
+    use crate::openssl::ffi::{IsOpensslPrivateKeyIdentity};
+
+    /// An OpenPGP key made of private keys of the store: a primary
+    /// key which certifies and signs, and an optional encryption
+    /// subkey. The identities are those of plain private keys, but
+    /// refer to keys reserved to OpenPGP. The user ID and the creation
+    /// time are part of the certificate, hence they must not change
+    /// once published.
+    pub trait IsOpenpgpKeyParams {
+        type PrivateKeyIdentity : IsOpensslPrivateKeyIdentity;
+
+        fn primary_key_identity(&self) -> &Self::PrivateKeyIdentity;
+        fn subkey_identity(&self) -> Result<Option<&Self::PrivateKeyIdentity>, Error>;
+        fn user_id(&self) -> &String;
+        /// The creation time of the keys, in the RFC 3339 format.
+        fn creation_time(&self) -> &String;
+    }
+}
+
+// The kinds of the keys of an OpenPGP key, see 'ReservedKeyIdentity'
+const K_PRIMARY_KEY_KIND : &str = "openpgp";
+const K_SUBKEY_KIND : &str = "openpgp-subkey";
+
+// Packet tags (RFC 4880, 4.3)
+const K_TAG_SIGNATURE : u8 = 2;
+const K_TAG_PUBLIC_KEY : u8 = 6;
+const K_TAG_USER_ID : u8 = 13;
+const K_TAG_PUBLIC_SUBKEY : u8 = 14;
+
+// Public key algorithms (RFC 4880, 9.1 and RFC 6637)
+const K_ALGORITHM_RSA : u8 = 1;
+const K_ALGORITHM_ECDH : u8 = 18;
+const K_ALGORITHM_ECDSA : u8 = 19;
+const K_ALGORITHM_EDDSA : u8 = 22;
+
+// Hash algorithms (RFC 4880, 9.4)
+const K_HASH_SHA256 : u8 = 8;
+const K_HASH_SHA384 : u8 = 9;
+const K_HASH_SHA512 : u8 = 10;
+
+// Symmetric algorithms (RFC 4880, 9.2)
+const K_SYMMETRIC_AES128 : u8 = 7;
+const K_SYMMETRIC_AES192 : u8 = 8;
+const K_SYMMETRIC_AES256 : u8 = 9;
+
+// Signature types (RFC 4880, 5.2.1)
+const K_SIGNATURE_BINARY : u8 = 0x00;
+const K_SIGNATURE_POSITIVE_CERTIFICATION : u8 = 0x13;
+const K_SIGNATURE_SUBKEY_BINDING : u8 = 0x18;
+
+// Signature subpackets (RFC 4880, 5.2.3.1 and RFC 9580)
+const K_SUBPACKET_CREATION_TIME : u8 = 2;
+const K_SUBPACKET_PREFERRED_SYMMETRIC : u8 = 11;
+const K_SUBPACKET_ISSUER : u8 = 16;
+const K_SUBPACKET_PREFERRED_HASH : u8 = 21;
+const K_SUBPACKET_KEY_FLAGS : u8 = 27;
+const K_SUBPACKET_FEATURES : u8 = 30;
+const K_SUBPACKET_ISSUER_FINGERPRINT : u8 = 33;
+
+// Key flags (RFC 4880, 5.2.3.21)
+const K_FLAGS_CERTIFY_SIGN : u8 = 0x03;
+const K_FLAGS_ENCRYPT : u8 = 0x0c;
+
+// The DER encoded OIDs of the curves, without the tag and the length
+const K_OID_ED25519 : &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+const K_OID_CURVE25519 : &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x97, 0x55, 0x01, 0x05, 0x01];
+const K_OID_P256 : &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const K_OID_P384 : &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+const K_OID_P521 : &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x23];
+
+/// A public key packet body (RFC 4880, 5.5.2) of a key of the store,
+/// with the fingerprint derived from it.
+struct PublicKeyPacket {
+    body: Vec<u8>
+}
+
+impl PublicKeyPacket {
+
+    /// Build the packet of 'key'. Primary keys must be able to sign,
+    /// whereas subkeys are only used for encryption.
+    fn new(key: &Key, creation_time: u32, primary: bool) -> Result<PublicKeyPacket, Error> {
+
+        let key_type = key.key_type()?;
+        Self::check_key_type(key_type, primary)?;
+
+        let mut body = vec![4];
+        body.extend_from_slice(&creation_time.to_be_bytes());
+
+        match (key_type, primary) {
+            (Type::RsaKey, _) => {
+                let rsa = key.pkey.rsa()?;
+                body.push(K_ALGORITHM_RSA);
+                put_mpi(&mut body, &rsa.n().to_vec());
+                put_mpi(&mut body, &rsa.e().to_vec());
+            },
+            (Type::EcP256Key | Type::EcP384Key | Type::EcP521Key, _) => {
+                let ec = key.pkey.ec_key()?;
+                let mut ctx = openssl::bn::BigNumContext::new()?;
+                let point = ec.public_key().to_bytes(
+                    ec.group(),
+                    openssl::ec::PointConversionForm::UNCOMPRESSED,
+                    &mut ctx
+                )?;
+                body.push(if primary { K_ALGORITHM_ECDSA } else { K_ALGORITHM_ECDH });
+                put_oid(&mut body, nist_curve_oid(key_type));
+                put_mpi(&mut body, &point);
+                if !primary {
+                    put_kdf_parameters(&mut body, key_type);
+                }
+            },
+            (Type::Ed25519Key, true) => {
+                body.push(K_ALGORITHM_EDDSA);
+                put_oid(&mut body, K_OID_ED25519);
+                put_mpi(&mut body, &[&[0x40], &key.public_raw()?[..]].concat());
+            },
+            (Type::X25519Key, false) => {
+                body.push(K_ALGORITHM_ECDH);
+                put_oid(&mut body, K_OID_CURVE25519);
+                put_mpi(&mut body, &[&[0x40], &key.public_raw()?[..]].concat());
+                put_kdf_parameters(&mut body, key_type);
+            },
+            _ => return Err(Error::internal(format!("Unexpected OpenPGP key type '{}'.", key_type.name())))
+        }
+
+        Ok(PublicKeyPacket { body })
+    }
+
+    /// Check that keys of the given type can be a primary key or an
+    /// encryption subkey, before any key of that type is generated.
+    fn check_key_type(key_type: Type, primary: bool) -> Result<(), Error> {
+
+        match (key_type, primary) {
+            (Type::RsaKey | Type::EcP256Key | Type::EcP384Key | Type::EcP521Key, _) => Ok(()),
+            (Type::Ed25519Key, true) | (Type::X25519Key, false) => Ok(()),
+            (_, true) => Err(Error::invalid_argument(format!(
+                "Keys of type '{}' cannot be the primary key of an OpenPGP key. The supported types are 'ed25519', 'rsa' and the 'ec' types.",
+                key_type.name()
+            ))),
+            (_, false) => Err(Error::invalid_argument(format!(
+                "Keys of type '{}' cannot be the encryption subkey of an OpenPGP key. The supported types are 'x25519', 'rsa' and the 'ec' types.",
+                key_type.name()
+            )))
+        }
+    }
+
+    /// The packet as hashed by signatures and fingerprints.
+    fn hashed(&self) -> Vec<u8> {
+        [&[0x99], &(self.body.len() as u16).to_be_bytes()[..], &self.body].concat()
+    }
+
+    /// The V4 fingerprint (RFC 4880, 12.2).
+    fn fingerprint(&self) -> [u8; 20] {
+        sha1(&self.hashed())
+    }
+
+    fn key_id(&self) -> [u8; 8] {
+        let mut key_id = [0; 8];
+        key_id.copy_from_slice(&self.fingerprint()[12..]);
+        key_id
+    }
+}
+
+fn nist_curve_oid(key_type: Type) -> &'static [u8] {
+
+    match key_type {
+        Type::EcP384Key => K_OID_P384,
+        Type::EcP521Key => K_OID_P521,
+        _ => K_OID_P256
+    }
+}
+
+/// Append the KDF parameters of an ECDH key (RFC 6637, 9), using
+/// the hash and cipher sizes recommended for the curve.
+fn put_kdf_parameters(buffer: &mut Vec<u8>, key_type: Type) {
+
+    let (hash, symmetric) = match key_type {
+        Type::EcP384Key => (K_HASH_SHA384, K_SYMMETRIC_AES192),
+        Type::EcP521Key => (K_HASH_SHA512, K_SYMMETRIC_AES256),
+        _ => (K_HASH_SHA256, K_SYMMETRIC_AES128)
+    };
+    buffer.extend_from_slice(&[3, 1, hash, symmetric]);
+}
+
+fn put_oid(buffer: &mut Vec<u8>, oid: &[u8]) {
+    buffer.push(oid.len() as u8);
+    buffer.extend_from_slice(oid);
+}
+
+/// Append a multiprecision integer (RFC 4880, 3.2), given
+/// its big-endian magnitude.
+fn put_mpi(buffer: &mut Vec<u8>, magnitude: &[u8]) {
+
+    let start = magnitude.iter().position(|b| *b != 0).unwrap_or(magnitude.len());
+    let magnitude = &magnitude[start..];
+    let bits = magnitude.first()
+        .map(|b| (magnitude.len() - 1) * 8 + (8 - b.leading_zeros() as usize))
+        .unwrap_or(0);
+
+    buffer.extend_from_slice(&(bits as u16).to_be_bytes());
+    buffer.extend_from_slice(magnitude);
+}
+
+/// Wrap a packet body in a packet with a new format header (RFC 4880, 4.2.2).
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+
+    let mut packet = vec![0xc0 | tag];
+    let length = body.len();
+
+    if length < 192 {
+        packet.push(length as u8);
+    } else if length < 8384 {
+        let length = length - 192;
+        packet.extend_from_slice(&[((length >> 8) + 192) as u8, length as u8]);
+    } else {
+        packet.push(0xff);
+        packet.extend_from_slice(&(length as u32).to_be_bytes());
+    }
+
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn subpacket(kind: u8, data: &[u8]) -> Vec<u8> {
+    [&[(data.len() + 1) as u8, kind], data].concat()
+}
+
+/// ASCII armor (RFC 4880, 6.2) with the given block type,
+/// such as "PUBLIC KEY BLOCK".
+fn armor(block: &str, data: &[u8]) -> String {
+
+    let mut crc : u32 = 0xb704ce;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    let crc = &(crc & 0xffffff).to_be_bytes()[1..];
+
+    let base64 = encoding::to_base64(data);
+    let lines: Vec<&str> = base64.as_bytes()
+        .chunks(64)
+        .map(|line| std::str::from_utf8(line).unwrap_or_default())
+        .collect();
+
+    format!(
+        "-----BEGIN PGP {block}-----\n\n{}\n={}\n-----END PGP {block}-----\n",
+        lines.join("\n"),
+        encoding::to_base64(crc)
+    )
+}
+
+/// How a key of the store produces OpenPGP signatures.
+struct SigningScheme {
+    algorithm: u8,
+    hash: u8,
+    digest: MessageDigest,
+    signature_algorithm: SignatureAlgorithm
+}
+
+impl SigningScheme {
+
+    fn for_key_type(key_type: Type) -> Result<SigningScheme, Error> {
+
+        let scheme = |algorithm, hash, digest, signature_algorithm| SigningScheme {
+            algorithm,
+            hash,
+            digest,
+            signature_algorithm
+        };
+
+        match key_type {
+            Type::RsaKey => Ok(scheme(K_ALGORITHM_RSA, K_HASH_SHA256, MessageDigest::sha256(), SignatureAlgorithm::RsaPkcs1Sha256)),
+            Type::EcP256Key => Ok(scheme(K_ALGORITHM_ECDSA, K_HASH_SHA256, MessageDigest::sha256(), SignatureAlgorithm::EcdsaSha256)),
+            Type::EcP384Key => Ok(scheme(K_ALGORITHM_ECDSA, K_HASH_SHA384, MessageDigest::sha384(), SignatureAlgorithm::EcdsaSha384)),
+            Type::EcP521Key => Ok(scheme(K_ALGORITHM_ECDSA, K_HASH_SHA512, MessageDigest::sha512(), SignatureAlgorithm::EcdsaSha512)),
+            Type::Ed25519Key => Ok(scheme(K_ALGORITHM_EDDSA, K_HASH_SHA256, MessageDigest::sha256(), SignatureAlgorithm::Ed25519)),
+            _ => Err(Error::invalid_argument(format!(
+                "Keys of type '{}' cannot produce OpenPGP signatures.",
+                key_type.name()
+            )))
+        }
+    }
+}
+
+/// The OpenPGP key of some parameters, as read from the store.
+struct OpenpgpKey<'a, T : ffi::IsOpenpgpKeyParams> {
+    params: &'a T,
+    creation_time: u32,
+    primary: PublicKeyPacket,
+    primary_scheme: SigningScheme,
+    subkey: Option<PublicKeyPacket>
+}
+
+impl CryptoNix {
+
+    fn openpgp_key<'a, T : ffi::IsOpenpgpKeyParams>(&self, params: &'a T) -> Result<OpenpgpKey<'a, T>, Error> {
+
+        let creation_time = unix_time_rfc3339(params.creation_time())
+            .and_then(|seconds| u32::try_from(seconds).map_err(|_| Error::invalid_argument(format!(
+                "The creation time of an OpenPGP key must be between 1970 and 2106, found '{}'.",
+                params.creation_time()
+            ))))
+            .context("while reading the 'creation-time' parameter")?;
+
+        // The key types are checked first so no key is generated
+        // for an identity which cannot be part of an OpenPGP key.
+        let check_key_type = |key_identity: &T::PrivateKeyIdentity, primary: bool| {
+            crate::openssl::ffi::algorithm(key_identity)
+                .and_then(|algorithm| PublicKeyPacket::check_key_type(algorithm.key_type, primary))
+                .with_context(|| format!("while reading the {} '{}'", if primary { "primary key" } else { "subkey" }, key_identity.key_id()))
+        };
+        check_key_type(params.primary_key_identity(), true)?;
+        if let Some(subkey_identity) = params.subkey_identity()? {
+            check_key_type(subkey_identity, false)?;
+        }
+
+        let primary_key = self.openssl_private_key(&ReservedKeyIdentity::new(K_PRIMARY_KEY_KIND, params.primary_key_identity()))?;
+        let primary = PublicKeyPacket::new(&primary_key, creation_time, true)
+            .with_context(|| format!("while reading the primary key '{}'", params.primary_key_identity().key_id()))?;
+        let primary_scheme = SigningScheme::for_key_type(primary_key.key_type()?)?;
+
+        let subkey = match params.subkey_identity()? {
+            Some(subkey_identity) => {
+                let subkey = self.openssl_private_key(&ReservedKeyIdentity::new(K_SUBKEY_KIND, subkey_identity))?;
+                Some(PublicKeyPacket::new(&subkey, creation_time, false)
+                    .with_context(|| format!("while reading the subkey '{}'", subkey_identity.key_id()))?)
+            },
+            None => None
+        };
+
+        Ok(OpenpgpKey { params, creation_time, primary, primary_scheme, subkey })
+    }
+
+    /// Build a V4 signature packet (RFC 4880, 5.2.3) made with the
+    /// primary key over 'hashed_data', which precedes the signature
+    /// fields in the hash as defined by the signature type.
+    fn openpgp_signature<T : ffi::IsOpenpgpKeyParams>(
+        &self,
+        key: &OpenpgpKey<T>,
+        signature_type: u8,
+        created: u32,
+        extra_subpackets: &[Vec<u8>],
+        hashed_data: &[u8]
+    ) -> Result<Vec<u8>, Error> {
+
+        let scheme = &key.primary_scheme;
+        let fingerprint = key.primary.fingerprint();
+
+        let mut subpackets = [
+            subpacket(K_SUBPACKET_CREATION_TIME, &created.to_be_bytes()),
+            subpacket(K_SUBPACKET_ISSUER_FINGERPRINT, &[&[4], &fingerprint[..]].concat())
+        ].concat();
+        subpackets.extend(extra_subpackets.concat());
+
+        let mut hashed = vec![4, signature_type, scheme.algorithm, scheme.hash];
+        hashed.extend_from_slice(&(subpackets.len() as u16).to_be_bytes());
+        hashed.extend_from_slice(&subpackets);
+
+        let trailer = [&[4, 0xff], &(hashed.len() as u32).to_be_bytes()[..]].concat();
+        let input = [hashed_data, &hashed, &trailer].concat();
+        let digest = hash(scheme.digest, &input)?;
+
+        // EdDSA signs the digest (RFC 4880bis, 5.2.4), whereas the
+        // other algorithms hash the input themselves.
+        let primary_identity = &ReservedKeyIdentity::new(K_PRIMARY_KEY_KIND, key.params.primary_key_identity());
+        let mut mpis = Vec::new();
+        match scheme.signature_algorithm {
+            SignatureAlgorithm::Ed25519 => {
+                let signature = self.openssl_sign(primary_identity, Some(scheme.signature_algorithm), &digest)?;
+                put_mpi(&mut mpis, &signature[..32]);
+                put_mpi(&mut mpis, &signature[32..]);
+            },
+            SignatureAlgorithm::RsaPkcs1Sha256 => {
+                let signature = self.openssl_sign(primary_identity, Some(scheme.signature_algorithm), &input)?;
+                put_mpi(&mut mpis, &signature);
+            },
+            _ => {
+                let signature = self.openssl_sign(primary_identity, Some(scheme.signature_algorithm), &input)?;
+                let signature = EcdsaSig::from_der(&signature)?;
+                put_mpi(&mut mpis, &signature.r().to_vec());
+                put_mpi(&mut mpis, &signature.s().to_vec());
+            }
+        }
+
+        let unhashed = subpacket(K_SUBPACKET_ISSUER, &key.primary.key_id());
+
+        let mut body = hashed;
+        body.extend_from_slice(&(unhashed.len() as u16).to_be_bytes());
+        body.extend_from_slice(&unhashed);
+        body.extend_from_slice(&digest[..2]);
+        body.extend_from_slice(&mpis);
+
+        Ok(packet(K_TAG_SIGNATURE, &body))
+    }
+
+    /// The ASCII armored OpenPGP certificate (transferable public key)
+    /// of the given parameters: the primary key, the user ID and the
+    /// optional encryption subkey, with their self-signatures. Every
+    /// signature is dated at the creation time, so the certificate is
+    /// always the same.
+    pub fn openpgp_certificate<T : ffi::IsOpenpgpKeyParams>(&self, params: &T) -> Result<String, Error> {
+
+        let key = self.openpgp_key(params)?;
+        let user_id = params.user_id().as_bytes();
+
+        let mut certificate = packet(K_TAG_PUBLIC_KEY, &key.primary.body);
+        certificate.extend(packet(K_TAG_USER_ID, user_id));
+
+        let certified = [
+            &key.primary.hashed()[..],
+            &[0xb4],
+            &(user_id.len() as u32).to_be_bytes(),
+            user_id
+        ].concat();
+        certificate.extend(self.openpgp_signature(
+            &key,
+            K_SIGNATURE_POSITIVE_CERTIFICATION,
+            key.creation_time,
+            &[
+                subpacket(K_SUBPACKET_KEY_FLAGS, &[K_FLAGS_CERTIFY_SIGN]),
+                subpacket(K_SUBPACKET_PREFERRED_SYMMETRIC, &[K_SYMMETRIC_AES256, K_SYMMETRIC_AES128]),
+                subpacket(K_SUBPACKET_PREFERRED_HASH, &[K_HASH_SHA512, K_HASH_SHA256]),
+                subpacket(K_SUBPACKET_FEATURES, &[0x01])
+            ],
+            &certified
+        ).context("while certifying the user ID of the OpenPGP key")?);
+
+        if let Some(subkey) = key.subkey.as_ref() {
+            certificate.extend(packet(K_TAG_PUBLIC_SUBKEY, &subkey.body));
+            certificate.extend(self.openpgp_signature(
+                &key,
+                K_SIGNATURE_SUBKEY_BINDING,
+                key.creation_time,
+                &[subpacket(K_SUBPACKET_KEY_FLAGS, &[K_FLAGS_ENCRYPT])],
+                &[key.primary.hashed(), subkey.hashed()].concat()
+            ).context("while binding the subkey of the OpenPGP key")?);
+        }
+
+        Ok(armor("PUBLIC KEY BLOCK", &certificate))
+    }
+
+    /// The fingerprint of the primary key, as 40 uppercase
+    /// hexadecimal digits.
+    pub fn openpgp_fingerprint<T : ffi::IsOpenpgpKeyParams>(&self, params: &T) -> Result<String, Error> {
+        let key = self.openpgp_key(params)?;
+        Ok(encoding::to_hex(&key.primary.fingerprint()).to_uppercase())
+    }
+
+    /// An ASCII armored detached signature over 'data' made with the
+    /// primary key, as checked by 'gpg --verify'. The signature is dated
+    /// at 'date' (in the RFC 3339 format), or at the creation time of
+    /// the key, so signing the same data again gives the same signature.
+    pub fn openpgp_sign<T : ffi::IsOpenpgpKeyParams>(
+        &self,
+        params: &T,
+        data: &[u8],
+        date: Option<&str>
+    ) -> Result<String, Error> {
+
+        let key = self.openpgp_key(params)?;
+        let created = match date {
+            Some(date) => unix_time_rfc3339(date)
+                .and_then(|seconds| u32::try_from(seconds)
+                    .ok()
+                    .filter(|seconds| *seconds >= key.creation_time)
+                    .ok_or_else(|| Error::invalid_argument(format!(
+                        "The date of an OpenPGP signature must be after the creation of the key and before 2106, found '{date}'."
+                    ))))
+                .context("while reading the 'date' parameter")?,
+            None => key.creation_time
+        };
+
+        let signature = self.openpgp_signature(&key, K_SIGNATURE_BINARY, created, &[], data)
+            .context("while signing data with the OpenPGP key")?;
+        Ok(armor("SIGNATURE", &signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+    use std::os::unix::fs::{PermissionsExt};
+    use std::path::{Path};
+    use std::process::{Command, Output};
+
+    use super::*;
+    use crate::openssl::{PrivateKeyIdentity};
+    use crate::testing::{TempDir, TempStore, assert_reserves_the_key, identity};
+
+    struct KeyParams {
+        primary_key_identity: PrivateKeyIdentity,
+        subkey_identity: Option<PrivateKeyIdentity>,
+        user_id: String,
+        creation_time: String
+    }
+
+    impl ffi::IsOpenpgpKeyParams for KeyParams {
+        type PrivateKeyIdentity = PrivateKeyIdentity;
+
+        fn primary_key_identity(&self) -> &PrivateKeyIdentity {
+            &self.primary_key_identity
+        }
+
+        fn subkey_identity(&self) -> Result<Option<&PrivateKeyIdentity>, Error> {
+            Ok(self.subkey_identity.as_ref())
+        }
+
+        fn user_id(&self) -> &String {
+            &self.user_id
+        }
+
+        fn creation_time(&self) -> &String {
+            &self.creation_time
+        }
+    }
+
+    fn key_params(primary_type: &str, subkey_type: &str) -> KeyParams {
+        KeyParams {
+            primary_key_identity: identity(primary_type, "name=release"),
+            subkey_identity: Some(identity(subkey_type, "name=release")),
+            user_id: "Release Signing <release@example.org>".to_string(),
+            creation_time: "2024-01-01T00:00:00Z".to_string()
+        }
+    }
+
+    fn gpg(home: &Path, args: &[&str]) -> Output {
+        Command::new("gpg")
+            .arg("--homedir")
+            .arg(home)
+            .args(["--batch", "--no-tty", "--status-fd", "1"])
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn reserves_the_keys_to_openpgp() {
+        // Both keys have the same identity, but are distinct
+        let params = key_params("rsa", "rsa");
+        let creation_time = 1704067200;
+
+        for primary in [true, false] {
+            assert_reserves_the_key(
+                &params.primary_key_identity,
+                |crypto_nix| {
+                    let key = crypto_nix.openpgp_key(&params).unwrap();
+                    let packet = if primary { key.primary } else { key.subkey.unwrap() };
+                    encoding::to_hex(&packet.body)
+                },
+                |key| encoding::to_hex(&PublicKeyPacket::new(key, creation_time, primary).unwrap().body)
+            );
+        }
+
+        let store = TempStore::new();
+        let key = store.open().openpgp_key(&params).unwrap();
+        assert_ne!(key.primary.body, key.subkey.unwrap().body);
+    }
+
+    #[test]
+    fn rejects_key_types_and_dates_before_generating_keys() {
+        let store = TempStore::new();
+        let crypto_nix = store.open();
+
+        for (params, expected) in [
+            (key_params("x25519", "x25519"), "cannot be the primary key of an OpenPGP key"),
+            (key_params("ed25519", "ed25519"), "cannot be the encryption subkey of an OpenPGP key")
+        ] {
+            let error = crypto_nix.openpgp_certificate(&params).err().unwrap();
+            assert!(error.message().contains(expected), "{}", error.message());
+
+            for (kind, key_identity) in [
+                (K_PRIMARY_KEY_KIND, &params.primary_key_identity),
+                (K_SUBKEY_KIND, params.subkey_identity.as_ref().unwrap())
+            ] {
+                assert!(crypto_nix.get_key(&ReservedKeyIdentity::new(kind, key_identity)).unwrap().is_none());
+            }
+        }
+
+        let params = KeyParams { creation_time: "2024-01-01".to_string(), ..key_params("ed25519", "x25519") };
+        let error = crypto_nix.openpgp_certificate(&params).err().unwrap();
+        assert!(error.message().contains("'creation-time'"), "{}", error.message());
+        assert!(error.message().contains("while parsing the date '2024-01-01'"), "{}", error.message());
+
+        let params = key_params("ed25519", "x25519");
+        for date in ["yesterday", "2023-12-31T23:59:59Z"] {
+            let error = crypto_nix.openpgp_sign(&params, b"release", Some(date)).err().unwrap();
+            assert!(error.message().contains("'date'"), "{}", error.message());
+        }
+    }
+
+    #[test]
+    fn certificates_and_signatures_are_checked_by_gpg() {
+        if Command::new("gpg").arg("--version").output().is_err() {
+            eprintln!("gpg is not available, the OpenPGP interoperability is not checked.");
+            return;
+        }
+
+        let store = TempStore::new();
+        let crypto_nix = store.open();
+
+        for (primary_type, subkey_type) in [("ed25519", "x25519"), ("ec-p256", "ec-p256"), ("ec-p384", "ec-p384"), ("rsa", "rsa")] {
+            let params = key_params(primary_type, subkey_type);
+            let certificate = crypto_nix.openpgp_certificate(&params).unwrap();
+            let signature = crypto_nix.openpgp_sign(&params, b"release manifest", None).unwrap();
+            let fingerprint = crypto_nix.openpgp_fingerprint(&params).unwrap();
+
+            let home = TempDir::new();
+            fs::set_permissions(&home.path, fs::Permissions::from_mode(0o700)).unwrap();
+            fs::write(home.path.join("release.asc"), &certificate).unwrap();
+            fs::write(home.path.join("manifest"), b"release manifest").unwrap();
+            fs::write(home.path.join("tampered"), b"tampered manifest").unwrap();
+            fs::write(home.path.join("manifest.sig"), &signature).unwrap();
+
+            let import = gpg(&home.path, &["--import", home.path.join("release.asc").to_str().unwrap()]);
+            assert!(import.status.success(), "{}", String::from_utf8_lossy(&import.stderr));
+
+            // The self-signatures are checked on import, which drops the
+            // user ID and the subkey if their signature is invalid
+            let listing = gpg(&home.path, &["--with-colons", "--list-keys", &fingerprint]);
+            let listing = String::from_utf8(listing.stdout).unwrap();
+            assert!(listing.contains(&format!("fpr:::::::::{fingerprint}:")), "{listing}");
+            assert!(listing.lines().any(|line| line.starts_with("uid:") && line.contains("Release Signing")), "{listing}");
+            assert!(listing.lines().any(|line| line.starts_with("sub:") && line.split(':').nth(11) == Some("e")), "{listing}");
+
+            let signature_path = home.path.join("manifest.sig");
+            let verify = gpg(&home.path, &["--verify", signature_path.to_str().unwrap(), home.path.join("manifest").to_str().unwrap()]);
+            let status = String::from_utf8(verify.stdout).unwrap();
+            assert!(verify.status.success() && status.contains(&format!("[GNUPG:] VALIDSIG {fingerprint}")), "{status}");
+
+            let tampered = gpg(&home.path, &["--verify", signature_path.to_str().unwrap(), home.path.join("tampered").to_str().unwrap()]);
+            assert!(!tampered.status.success());
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use std::ffi::{CStr, CString, c_char, c_int, c_ulong, c_void};
+
+use argon2::{Argon2};
+use argon2::password_hash::{PasswordHasher, SaltString};
+use openssl::rand::{rand_bytes};
+
+use crate::error::{Error, ErrorContext};
+use crate::foundations::{CryptoNix};
+use crate::store::{IsCryptoStoreKey, StoreHasher};
+
+/// See the 'ffi' module of 'crate::openssl' for the reason why the
+/// identities are described by traits.
+pub mod ffi {
+
+    use crate::error::*;
+    use crate::password::{Password, PasswordAlphabet, PasswordPolicy};
+    use crate::store::{IsCryptoStoreKey, StoreHasher};
+
+    pub trait IsPasswordIdentity : IsCryptoStoreKey<Value = Password> {
+        fn key_id(&self) -> &String;
+
+        // The number of characters of the password, if not the default.
+        fn length(&self) -> Result<Option<u32>, Error>;
+
+        // The name of the alphabet of the password, if not the default.
+        fn alphabet(&self) -> Result<Option<&String>, Error>;
+    }
+
+    const K_DEFAULT_LENGTH : u32 = 24;
+    const K_MIN_LENGTH : u32 = 8;
+    const K_MAX_LENGTH : u32 = 256;
+
+    /// The policy under which the password of an identity is generated.
+    pub fn password_policy<T : IsPasswordIdentity>(password_identity: &T) -> Result<PasswordPolicy, Error> {
+
+        let length = password_identity.length()?.unwrap_or(K_DEFAULT_LENGTH);
+
+        if !(K_MIN_LENGTH..=K_MAX_LENGTH).contains(&length) {
+            return Err(Error::invalid_argument(format!(
+                "The length of a password must be between {K_MIN_LENGTH} and {K_MAX_LENGTH} characters, found {length}."
+            )));
+        }
+
+        let alphabet = password_identity.alphabet()?
+            .map(|alphabet| PasswordAlphabet::try_from(alphabet.as_str()))
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(PasswordPolicy { length: length as usize, alphabet })
+    }
+
+    /// Compute the key under which the password of an identity is saved
+    /// in the store. As for the other secrets, every front end must use
+    /// this function. The policy is only hashed if it is not the default.
+    pub fn identity_store_key<T : IsPasswordIdentity>(password_identity: &T, mut hasher: StoreHasher) -> Vec<u8> {
+
+        // The NUL byte separates these keys from the ones of the
+        // private keys, whose types never contain a NUL byte.
+        hasher.update(b"password\0");
+        hasher.update(password_identity.key_id().as_bytes());
+
+        if let Ok(policy) = password_policy(password_identity) {
+            let mut params = Vec::new();
+            if policy.length != K_DEFAULT_LENGTH as usize {
+                params.push(format!("length={}", policy.length));
+            }
+            if policy.alphabet != PasswordAlphabet::default() {
+                params.push(format!("alphabet={}", policy.alphabet.name()));
+            }
+            if !params.is_empty() {
+                hasher.update(b"\0");
+                hasher.update(params.join("&").as_bytes());
+            }
+        }
+
+        Vec::from(hasher.finish())
+    }
+}
+
+/// The characters from which a password is drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PasswordAlphabet {
+    #[default]
+    Alphanumeric,
+    LowercaseAlphanumeric,
+    Digits,
+    Hex,
+    /// The printable ASCII characters, except the space.
+    Printable
+}
+
+/// The names used to refer to each alphabet from Nix.
+const K_PASSWORD_ALPHABETS : &[(&str, PasswordAlphabet)] = &[
+    ("alphanumeric", PasswordAlphabet::Alphanumeric),
+    ("lowercase-alphanumeric", PasswordAlphabet::LowercaseAlphanumeric),
+    ("digits", PasswordAlphabet::Digits),
+    ("hex", PasswordAlphabet::Hex),
+    ("printable", PasswordAlphabet::Printable)
+];
+
+impl PasswordAlphabet {
+
+    /// The name used to refer to this alphabet from Nix.
+    pub fn name(&self) -> &'static str {
+        K_PASSWORD_ALPHABETS.iter()
+            .find(|(_, alphabet)| alphabet == self)
+            .map(|(name, _)| *name)
+            .unwrap_or("unknown")
+    }
+
+    fn characters(&self) -> Vec<u8> {
+
+        let range = |first: u8, last: u8| (first..=last).collect::<Vec<u8>>();
+
+        match self {
+            PasswordAlphabet::Alphanumeric => [range(b'A', b'Z'), range(b'a', b'z'), range(b'0', b'9')].concat(),
+            PasswordAlphabet::LowercaseAlphanumeric => [range(b'a', b'z'), range(b'0', b'9')].concat(),
+            PasswordAlphabet::Digits => range(b'0', b'9'),
+            PasswordAlphabet::Hex => [range(b'0', b'9'), range(b'a', b'f')].concat(),
+            PasswordAlphabet::Printable => range(b'!', b'~')
+        }
+    }
+}
+
+impl TryFrom<&str> for PasswordAlphabet {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<PasswordAlphabet, Error> {
+
+        K_PASSWORD_ALPHABETS.iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, alphabet)| *alphabet)
+            .ok_or_else(|| {
+                let names: Vec<&str> = K_PASSWORD_ALPHABETS.iter().map(|(name, _)| *name).collect();
+                Error::invalid_argument(format!(
+                    "The value '{value}' is not a known password alphabet. The supported alphabets are: {}.",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// The length and the alphabet of a generated password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub alphabet: PasswordAlphabet
+}
+
+/// A password generated by CryptoNix. It never leaves CryptoNix,
+/// except through the 'cryptonix' command, and Nix only obtains
+/// its hashes.
+#[derive(Clone)]
+pub struct Password {
+    text: String
+}
+
+impl Password {
+
+    /// Generate a password under 'policy'. Every character is drawn
+    /// uniformly from the alphabet, by rejecting the random bytes
+    /// which would bias the draw.
+    pub fn new(policy: &PasswordPolicy) -> Result<Password, Error> {
+
+        let characters = policy.alphabet.characters();
+        let limit = 256 - 256 % characters.len();
+        let mut text = String::with_capacity(policy.length);
+        let mut buffer = [0; 64];
+
+        while text.len() < policy.length {
+            rand_bytes(&mut buffer)?;
+            for byte in buffer.iter().map(|byte| *byte as usize).filter(|byte| *byte < limit) {
+                if text.len() < policy.length {
+                    text.push(characters[byte % characters.len()] as char);
+                }
+            }
+        }
+
+        Ok(Password { text })
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Password, Error> {
+        Ok(Password { text: String::from_utf8(bytes)? })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn satisfies(&self, policy: &PasswordPolicy) -> bool {
+        let characters = policy.alphabet.characters();
+        self.text.len() == policy.length && self.text.bytes().all(|c| characters.contains(&c))
+    }
+}
+
+/// The crypt schemes in which the hash of a password is given.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PasswordScheme {
+    #[default]
+    Yescrypt,
+    Sha512Crypt,
+    Bcrypt,
+    Argon2
+}
+
+/// The names used to refer to each scheme from Nix.
+const K_PASSWORD_SCHEMES : &[(&str, PasswordScheme)] = &[
+    ("yescrypt", PasswordScheme::Yescrypt),
+    ("sha512-crypt", PasswordScheme::Sha512Crypt),
+    ("bcrypt", PasswordScheme::Bcrypt),
+    ("argon2", PasswordScheme::Argon2)
+];
+
+/// The cost of bcrypt hashes. The default cost of libxcrypt is 5,
+/// which is too low for passwords hashed once and for all.
+const K_BCRYPT_COST : c_ulong = 12;
+
+/// bcrypt only uses the first 72 bytes of a password.
+const K_BCRYPT_MAX_LENGTH : usize = 72;
+
+/// The size of the salt of every scheme, in bytes.
+const K_SALT_LENGTH : usize = 16;
+
+/// The size of the memory given to libxcrypt, which must be at least
+/// the size of its 'struct crypt_data' (32 KiB as of libxcrypt 4.4).
+const K_CRYPT_DATA_SIZE : usize = 64 * 1024;
+
+impl PasswordScheme {
+
+    /// The name used to refer to this scheme from Nix.
+    pub fn name(&self) -> &'static str {
+        K_PASSWORD_SCHEMES.iter()
+            .find(|(_, scheme)| scheme == self)
+            .map(|(name, _)| *name)
+            .unwrap_or("unknown")
+    }
+
+    /// The prefix and the cost of the setting of a libxcrypt scheme.
+    /// A zero cost selects the default cost of libxcrypt.
+    fn crypt_setting(&self) -> Option<(&'static CStr, c_ulong)> {
+
+        match self {
+            PasswordScheme::Yescrypt => Some((c"$y$", 0)),
+            PasswordScheme::Sha512Crypt => Some((c"$6$", 0)),
+            PasswordScheme::Bcrypt => Some((c"$2b$", K_BCRYPT_COST)),
+            PasswordScheme::Argon2 => None
+        }
+    }
+}
+
+impl TryFrom<&str> for PasswordScheme {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<PasswordScheme, Error> {
+
+        K_PASSWORD_SCHEMES.iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, scheme)| *scheme)
+            .ok_or_else(|| {
+                let names: Vec<&str> = K_PASSWORD_SCHEMES.iter().map(|(name, _)| *name).collect();
+                Error::invalid_argument(format!(
+                    "The value '{value}' is not a known password hashing scheme. The supported schemes are: {}.",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+// The reentrant functions of libxcrypt, the crypt library used by
+// NixOS (and by glibc based distributions) to check passwords.
+#[link(name = "crypt")]
+unsafe extern "C" {
+    fn crypt_gensalt_rn(
+        prefix: *const c_char,
+        count: c_ulong,
+        rbytes: *const c_char,
+        nrbytes: c_int,
+        output: *mut c_char,
+        output_size: c_int
+    ) -> *mut c_char;
+
+    fn crypt_rn(
+        phrase: *const c_char,
+        setting: *const c_char,
+        data: *mut c_void,
+        size: c_int
+    ) -> *mut c_char;
+}
+
+/// Hash 'password' with libxcrypt. The setting is built from 'salt'
+/// rather than from random bytes, so the hash is deterministic.
+fn crypt(password: &str, prefix: &CStr, cost: c_ulong, salt: &[u8]) -> Result<String, Error> {
+
+    let password = CString::new(password)
+        .map_err(|_| Error::invalid_argument("A password cannot contain a NUL character.".to_string()))?;
+    let mut setting = vec![0 as c_char; 256];
+    let mut data = vec![0u8; K_CRYPT_DATA_SIZE];
+
+    // SAFETY: the buffers outlive the calls and their sizes are the
+    // ones given to libxcrypt, which returns NULL on failure.
+    unsafe {
+        let setting = crypt_gensalt_rn(
+            prefix.as_ptr(),
+            cost,
+            salt.as_ptr() as *const c_char,
+            salt.len() as c_int,
+            setting.as_mut_ptr(),
+            setting.len() as c_int
+        );
+        if setting.is_null() {
+            return Err(Error::configuration(format!(
+                "The crypt library does not support the prefix '{}'.",
+                prefix.to_string_lossy()
+            )));
+        }
+
+        let hash = crypt_rn(password.as_ptr(), setting, data.as_mut_ptr() as *mut c_void, data.len() as c_int);
+        if hash.is_null() || *hash == b'*' as c_char {
+            return Err(Error::internal(format!(
+                "The crypt library failed to hash the password with the prefix '{}'.",
+                prefix.to_string_lossy()
+            )));
+        }
+
+        Ok(CStr::from_ptr(hash).to_str()?.to_string())
+    }
+}
+
+/// An identity of a password built in Rust, such as the
+/// ones supplied on the command line.
+pub struct PasswordIdentity {
+    pub key_id: String,
+    pub length: Option<u32>,
+    pub alphabet: Option<String>
+}
+
+impl IsCryptoStoreKey for PasswordIdentity {
+    type Value = Password;
+
+    fn to_store_key_raw(&self, hasher: StoreHasher) -> Vec<u8> {
+        ffi::identity_store_key(self, hasher)
+    }
+
+    fn to_store_value_raw(value: &Password) -> Result<Vec<u8>, Error> {
+        Ok(value.as_str().as_bytes().to_vec())
+    }
+
+    fn from_store_value_raw(bytes: &Vec<u8>) -> Result<Password, Error> {
+        Password::from_bytes(bytes.clone())
+    }
+}
+
+impl ffi::IsPasswordIdentity for PasswordIdentity {
+
+    fn key_id(&self) -> &String {
+        &self.key_id
+    }
+
+    fn length(&self) -> Result<Option<u32>, Error> {
+        Ok(self.length)
+    }
+
+    fn alphabet(&self) -> Result<Option<&String>, Error> {
+        Ok(self.alphabet.as_ref())
+    }
+}
+
+impl CryptoNix {
+
+    /// Get the password of the given identity. If there is no password
+    /// associated with the identity, a fresh password is generated
+    /// under the policy of the identity and saved in the store.
+    pub fn password<T : ffi::IsPasswordIdentity>(&self, password_identity: &T) -> Result<Password, Error> {
+
+        let describe_identity = || format!("the password identity '{}'", password_identity.key_id());
+
+        let policy = ffi::password_policy(password_identity)
+            .with_context(|| format!("while reading the policy of {}", describe_identity()))?;
+
+        let check_policy = |password: Password| {
+            if password.satisfies(&policy) {
+                Ok(password)
+            } else {
+                Err(Error::invalid_argument(format!(
+                    "The password of {} does not satisfy the policy of the identity.",
+                    describe_identity()
+                )))
+            }
+        };
+
+        self.get_or_insert_with(
+            password_identity,
+            || format!("the password of {}", describe_identity()),
+            || Password::new(&policy)
+                .with_context(|| format!("while generating a new password for {}", describe_identity())),
+            check_policy
+        )
+    }
+
+    /// The salt of the hashes of a password. It is derived from the
+    /// salt of the store, the identity and the scheme, so the hash of
+    /// a password does not change from one evaluation to the next.
+    fn password_salt<T : ffi::IsPasswordIdentity>(&self, password_identity: &T, scheme: PasswordScheme) -> Vec<u8> {
+
+        let mut hasher = StoreHasher::init(&self.salt());
+        hasher.update(b"password salt\0");
+        hasher.update(scheme.name().as_bytes());
+        hasher.update(b"\0");
+
+        let mut salt = password_identity.to_store_key_raw(hasher);
+        salt.truncate(K_SALT_LENGTH);
+        salt
+    }
+
+    /// The hash of the password of the given identity in the crypt
+    /// format, as expected by 'users.users.<name>.hashedPassword'
+    /// for example. The password itself is never returned.
+    pub fn password_hash<T : ffi::IsPasswordIdentity>(
+        &self,
+        password_identity: &T,
+        scheme: PasswordScheme
+    ) -> Result<String, Error> {
+
+        let password = self.password(password_identity)?;
+        let salt = self.password_salt(password_identity, scheme);
+
+        let hash = match scheme.crypt_setting() {
+            Some((prefix, cost)) => {
+                if scheme == PasswordScheme::Bcrypt && password.as_str().len() > K_BCRYPT_MAX_LENGTH {
+                    return Err(Error::invalid_argument(format!(
+                        "bcrypt only uses the first {K_BCRYPT_MAX_LENGTH} characters of a password, use a shorter password or another scheme."
+                    )));
+                }
+                crypt(password.as_str(), prefix, cost, &salt)
+            },
+            None => {
+                let salt = SaltString::encode_b64(&salt)
+                    .map_err(|e| Error::internal(format!("Could not encode the salt of an argon2 hash: {e}")))?;
+                Argon2::default().hash_password(password.as_str().as_bytes(), &salt)
+                    .map(|hash| hash.to_string())
+                    .map_err(|e| Error::internal(format!("Could not compute an argon2 hash: {e}")))
+            }
+        };
+
+        hash.with_context(|| format!(
+            "while hashing the password identity '{}' with {}",
+            password_identity.key_id(),
+            scheme.name()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+    use super::*;
+    use crate::testing::{TempStore};
+
+    const K_SCHEMES : [PasswordScheme; 4] = [
+        PasswordScheme::Yescrypt,
+        PasswordScheme::Sha512Crypt,
+        PasswordScheme::Bcrypt,
+        PasswordScheme::Argon2
+    ];
+
+    fn identity(key_id: &str) -> PasswordIdentity {
+        PasswordIdentity {
+            key_id: key_id.to_string(),
+            length: None,
+            alphabet: None
+        }
+    }
+
+    /// Check 'password' against 'hash' as a login would: libxcrypt
+    /// hashes the password again with the hash as the setting.
+    fn accepts(hash: &str, password: &str) -> bool {
+
+        if hash.starts_with("$argon2") {
+            let hash = PasswordHash::new(hash).unwrap();
+            return Argon2::default().verify_password(password.as_bytes(), &hash).is_ok();
+        }
+
+        let password = CString::new(password).unwrap();
+        let setting = CString::new(hash).unwrap();
+        let mut data = vec![0u8; K_CRYPT_DATA_SIZE];
+
+        // SAFETY: as in 'crypt'
+        unsafe {
+            let rehashed = crypt_rn(password.as_ptr(), setting.as_ptr(), data.as_mut_ptr() as *mut c_void, data.len() as c_int);
+            !rehashed.is_null() && CStr::from_ptr(rehashed).to_str().unwrap() == hash
+        }
+    }
+
+    #[test]
+    fn hashes_accept_the_password() {
+        let store = TempStore::new();
+        let crypto_nix = store.open();
+        let identity = identity("name=alice");
+        let password = crypto_nix.password(&identity).unwrap();
+
+        for scheme in K_SCHEMES {
+            let hash = crypto_nix.password_hash(&identity, scheme).unwrap();
+            assert!(accepts(&hash, password.as_str()), "{}: {hash}", scheme.name());
+            assert!(!accepts(&hash, "not the password"), "{}: {hash}", scheme.name());
+        }
+    }
+
+    #[test]
+    fn hashes_are_stable_across_reopens() {
+        let store = TempStore::new();
+        let alice = identity("name=alice");
+        let bob = identity("name=bob");
+
+        let hashes: Vec<String> = K_SCHEMES.iter()
+            .map(|scheme| store.open().password_hash(&alice, *scheme).unwrap())
+            .collect();
+
+        let crypto_nix = store.open();
+        for (scheme, hash) in K_SCHEMES.iter().zip(hashes.iter()) {
+            assert_eq!(&crypto_nix.password_hash(&alice, *scheme).unwrap(), hash, "{}", scheme.name());
+            assert_ne!(
+                crypto_nix.password_salt(&alice, *scheme),
+                crypto_nix.password_salt(&bob, *scheme),
+                "{}", scheme.name()
+            );
+        }
+        assert_ne!(
+            crypto_nix.password_salt(&alice, PasswordScheme::Yescrypt),
+            crypto_nix.password_salt(&alice, PasswordScheme::Sha512Crypt)
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::{HashSet};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::error::*;
+use crate::openssl::pkey::{Algorithm, Key};
+use crate::store::{CryptoStore};
+
+/// A pool of private keys which are generated ahead of time by a
+/// background thread. Generating RSA keys is slow, so claiming a
+/// pre-generated key avoids blocking the evaluation on it. The keys
+/// are kept in the store, hence a pool filled by a previous run is
+/// used by the next one. Every algorithm and parameter set has its
+/// own pool, which is only filled once a key of that algorithm has
+/// been requested.
+pub struct KeyPool {
+    size: usize,
+    store: Arc<dyn CryptoStore + Send + Sync>,
+    /// The pools which are being refilled by a background thread.
+    refilling: Mutex<HashSet<String>>
+}
+
+impl KeyPool {
+
+    pub fn new(size: usize, store: Arc<dyn CryptoStore + Send + Sync>) -> Arc<KeyPool> {
+        Arc::new(KeyPool { size, store, refilling: Mutex::new(HashSet::new()) })
+    }
+
+    /// Take a pre-generated key of the given algorithm out of the
+    /// pool, if there is one. The pool is refilled in the background.
+    /// A claimed key is removed atomically from the store, so it can
+    /// only be bound to a single identity.
+    pub fn claim(self: &Arc<Self>, algorithm: &Algorithm) -> Result<Option<Key>, Error> {
+
+        let key = self.store.pool_claim(&algorithm.pool_name())?
+            .map(|pem| Key::key_from_pem(&pem))
+            .transpose()?;
+
+        self.refill(algorithm);
+        Ok(key)
+    }
+
+    /// Start a background thread which fills the pool of the given
+    /// algorithm, unless one is already running.
+    fn refill(self: &Arc<Self>, algorithm: &Algorithm) {
+
+        let name = algorithm.pool_name();
+        let mut refilling = self.refilling.lock().unwrap_or_else(|e| e.into_inner());
+
+        if !refilling.insert(name.clone()) {
+            return;
+        }
+
+        let pool = Arc::clone(self);
+        let thread_algorithm = algorithm.clone();
+        let thread_name = name.clone();
+
+        let spawned = thread::Builder::new()
+            .name(format!("cryptonix-pool-{name}"))
+            .spawn(move || {
+                // Failures are not reported as the pool is only an
+                // optimization. Keys are generated inline instead.
+                let _ = pool.fill(&thread_algorithm);
+                pool.refilling.lock().unwrap_or_else(|e| e.into_inner()).remove(&thread_name);
+            });
+
+        if spawned.is_err() {
+            refilling.remove(&name);
+        }
+    }
+
+    fn fill(&self, algorithm: &Algorithm) -> Result<(), Error> {
+
+        let name = algorithm.pool_name();
+
+        while self.store.pool_len(&name)? < self.size {
+            let key = Key::new(algorithm)?;
+            self.store.pool_push(&name, key.key_to_pem()?)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::store::{SledStore};
+    use crate::testing::{TempStore};
+
+    fn open_pool(temp: &TempStore, size: usize) -> (Arc<KeyPool>, Arc<SledStore>) {
+        let store = Arc::new(SledStore::open(temp.path.to_str().unwrap()).unwrap());
+        (KeyPool::new(size, Arc::clone(&store) as Arc<dyn CryptoStore + Send + Sync>), store)
+    }
+
+    fn wait_for_refill(pool: &KeyPool) {
+        let start = Instant::now();
+        while !pool.refilling.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(30), "The pool was not refilled in time");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn ed25519() -> Algorithm {
+        Algorithm::new("ed25519", None, None, None).unwrap()
+    }
+
+    #[test]
+    fn refills_the_pool_up_to_its_size() {
+        let temp = TempStore::new();
+        let (pool, store) = open_pool(&temp, 3);
+        let algorithm = ed25519();
+
+        assert!(pool.claim(&algorithm).unwrap().is_none());
+        wait_for_refill(&pool);
+        assert_eq!(store.pool_len(&algorithm.pool_name()).unwrap(), 3);
+
+        assert!(pool.claim(&algorithm).unwrap().is_some());
+        wait_for_refill(&pool);
+        assert_eq!(store.pool_len(&algorithm.pool_name()).unwrap(), 3);
+
+        let other = Algorithm::new("x25519", None, None, None).unwrap();
+        assert_eq!(store.pool_len(&other.pool_name()).unwrap(), 0);
+    }
+
+    #[test]
+    fn claims_every_key_once() {
+        let temp = TempStore::new();
+        let (pool, store) = open_pool(&temp, 8);
+        let algorithm = ed25519();
+        pool.fill(&algorithm).unwrap();
+
+        let claimers: Vec<_> = (0..16).map(|_| {
+            let pool = Arc::clone(&pool);
+            let algorithm = algorithm.clone();
+            thread::spawn(move || pool.claim(&algorithm).unwrap().map(|key| key.public_raw().unwrap()))
+        }).collect();
+        let claimed: Vec<Vec<u8>> = claimers.into_iter().filter_map(|claimer| claimer.join().unwrap()).collect();
+        wait_for_refill(&pool);
+
+        let remaining: Vec<Vec<u8>> = std::iter::from_fn(|| store.pool_claim(&algorithm.pool_name()).unwrap())
+            .map(|pem| Key::key_from_pem(&pem).unwrap().public_raw().unwrap())
+            .collect();
+
+        let distinct: HashSet<&Vec<u8>> = claimed.iter().chain(remaining.iter()).collect();
+        assert!(claimed.len() >= 8, "Expected the 8 pooled keys to be claimed, found {}", claimed.len());
+        assert_eq!(distinct.len(), claimed.len() + remaining.len());
+    }
+}
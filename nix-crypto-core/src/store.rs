@@ -1,4 +1,8 @@
 use openssl::sha::Sha256;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::{Duration};
 
 use crate::error::*;
 
@@ -38,6 +42,13 @@ pub trait IsCryptoStoreKey {
 /// symmetric keys, amoong other things.
 pub trait CryptoStore {
     fn get_raw(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Save 'value' under 'key', failing if the key already holds a
+    /// value. A store is opened by a single process, but it is shared
+    /// by all the threads of that process, such as the ones of a parallel
+    /// Nix evaluation. Two threads may therefore generate a secret for
+    /// the same identity at once, and the check must be atomic so only
+    /// one of them is saved. The other one must read the saved value.
     fn put_raw(&self, key: &[u8], value: Vec<u8>) -> Result<(), Error>;
 
     /// This function should return a salt that 'CryptoNix' will use
@@ -45,6 +56,19 @@ pub trait CryptoStore {
     /// and to remain constant whenever the same store is intialized upon
     /// different runs of the program.
     fn salt(&self) -> Vec<u8>;
+
+    /// Add a value to the pool with the given name. Pools hold
+    /// values, such as pre-generated private keys, which have not
+    /// been associated with any key of the store yet.
+    fn pool_push(&self, pool: &str, value: Vec<u8>) -> Result<(), Error>;
+
+    /// Remove a value from the pool with the given name. This must be
+    /// atomic, so a value is never handed out more than once, even
+    /// to different threads claiming a value at the same time.
+    fn pool_claim(&self, pool: &str) -> Result<Option<Vec<u8>>, Error>;
+
+    /// The number of values in the pool with the given name.
+    fn pool_len(&self, pool: &str) -> Result<usize, Error>;
 }
 
 /// The 'ErrorStore' represents a store that will fail
@@ -79,12 +103,24 @@ impl CryptoStore for ErrorStore {
     fn salt(&self) -> Vec<u8> {
         Vec::from(SALT)
     }
+
+    fn pool_push(&self, _pool: &str, _value: Vec<u8>) -> Result<(), Error> {
+        Err(self.error.clone())
+    }
+
+    fn pool_claim(&self, _pool: &str) -> Result<Option<Vec<u8>>, Error> {
+        Err(self.error.clone())
+    }
+
+    fn pool_len(&self, _pool: &str) -> Result<usize, Error> {
+        Err(self.error.clone())
+    }
 }
 
 /// The 'SledStore' implements a 'CryptoStore' using
 /// the 'sled' crate as the storage backend.
 pub struct SledStore {
-    sled_db : sled::Db
+    sled_db : Arc<sled::Db>
 }
 
 impl CryptoStore for SledStore {
@@ -98,11 +134,10 @@ impl CryptoStore for SledStore {
 
     fn put_raw(&self, key: &[u8], value: Vec<u8>) -> Result<(), Error> {
 
-        if self.sled_db.contains_key(key)? {
-            return Error::fail_with("Bug in CryptoNix. An attempt was made to replace an existing key in the store. Please report this issue.".to_string());
+        match self.sled_db.compare_and_swap(key, None as Option<&[u8]>, Some(value))? {
+            Ok(()) => Ok(()),
+            Err(_) => Err(Error::internal("An attempt was made to replace an existing key in the store.".to_string()))
         }
-        let _ = self.sled_db.insert(key, value)?;
-        Ok(())
     }
 
     fn salt(&self) -> Vec<u8> {
@@ -110,11 +145,71 @@ impl CryptoStore for SledStore {
         // saved in the sled store
         Vec::from(SALT)
     }
+
+    fn pool_push(&self, pool: &str, value: Vec<u8>) -> Result<(), Error> {
+        let id = self.sled_db.generate_id()?;
+        self.pool_tree(pool)?.insert(id.to_be_bytes(), value)?;
+        Ok(())
+    }
+
+    fn pool_claim(&self, pool: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.pool_tree(pool)?.pop_min()?.map(|(_, value)| value.to_vec()))
+    }
+
+    fn pool_len(&self, pool: &str) -> Result<usize, Error> {
+        Ok(self.pool_tree(pool)?.len())
+    }
 }
 
+const K_LOCK_ATTEMPTS : u32 = 100;
+const K_LOCK_RETRY_DELAY : Duration = Duration::from_millis(10);
+
+/// The 'sled' databases which have been opened by this process.
+/// A 'sled' database can only be opened once at a time as it holds
+/// a lock on its files. Therefore, 'CryptoNix' instances which refer
+/// to the same path share the same database handle. Weak references
+/// are kept so the database is still flushed and closed once the
+/// last store using it is dropped.
+static SLED_DATABASES : LazyLock<Mutex<HashMap<PathBuf, Weak<sled::Db>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
 impl SledStore {
+
+    /// Every pool is kept in its own tree, separate from
+    /// the default tree which holds the keys of the store.
+    fn pool_tree(&self, pool: &str) -> Result<sled::Tree, Error> {
+        Ok(self.sled_db.open_tree(format!("cryptonix-pool/{pool}"))?)
+    }
+
+    /// The flusher thread of a 'sled' database releases the lock on
+    /// its files shortly after the last handle is dropped. Opening the
+    /// database again right away may thus find it still locked, in
+    /// which case the attempt is repeated for a short while.
+    fn open_db(path: &Path) -> sled::Result<sled::Db> {
+
+        let mut attempts = 0;
+        loop {
+            match sled::open(path) {
+                Err(sled::Error::Io(e)) if attempts < K_LOCK_ATTEMPTS && e.to_string().contains("could not acquire lock") => {
+                    attempts += 1;
+                    std::thread::sleep(K_LOCK_RETRY_DELAY);
+                },
+                result => return result
+            }
+        }
+    }
+
     pub fn open(path: &str) -> Result<SledStore, Error> {
-        let db = sled::open(path)?;
+
+        let mut databases = SLED_DATABASES.lock().unwrap_or_else(|e| e.into_inner());
+        let path = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+
+        if let Some(db) = databases.get(&path).and_then(Weak::upgrade) {
+            return Ok(SledStore { sled_db: db });
+        }
+
+        let db = Arc::new(Self::open_db(&path)?);
+        databases.insert(path, Arc::downgrade(&db));
         Ok(SledStore { sled_db : db })
     }
 }
@@ -0,0 +1,719 @@
+use openssl::cipher::{Cipher};
+use openssl::cipher_ctx::{CipherCtx};
+use openssl::hash::{MessageDigest};
+use openssl::md::{Md};
+use openssl::pkey::{PKey};
+use openssl::rand::{rand_bytes};
+use openssl::sign::{Signer};
+
+use crate::error::{Error, ErrorContext};
+use crate::foundations::{CryptoNix};
+use crate::hkdf;
+use crate::store::{IsCryptoStoreKey, StoreHasher};
+
+/// See the 'ffi' module of 'crate::openssl' for the reason why the
+/// identities are described by traits.
+pub mod ffi {
+
+    use crate::error::*;
+    use crate::store::{IsCryptoStoreKey, StoreHasher};
+    use crate::symmetric::{SymmetricKey};
+
+    pub trait IsSymmetricKeyIdentity : IsCryptoStoreKey<Value = SymmetricKey> {
+        fn key_id(&self) -> &String;
+
+        // The length in bytes of the key, if not the default.
+        fn length(&self) -> Result<Option<u32>, Error>;
+
+        // The labels of the child key derived from the stored key,
+        // one label per level of derivation. If there are no labels,
+        // the identity refers to the stored key itself.
+        fn labels(&self) -> &Vec<String>;
+    }
+
+    const K_DEFAULT_LENGTH : u32 = 32;
+    const K_MIN_LENGTH : u32 = 16;
+    const K_MAX_LENGTH : u32 = 1024;
+
+    /// The length in bytes of the key of an identity.
+    pub fn key_length<T : IsSymmetricKeyIdentity>(key_identity: &T) -> Result<usize, Error> {
+
+        let length = key_identity.length()?.unwrap_or(K_DEFAULT_LENGTH);
+
+        if !(K_MIN_LENGTH..=K_MAX_LENGTH).contains(&length) {
+            return Err(Error::invalid_argument(format!(
+                "The length of a symmetric key must be between {K_MIN_LENGTH} and {K_MAX_LENGTH} bytes, found {length}."
+            )));
+        }
+
+        Ok(length as usize)
+    }
+
+    /// Compute the key under which the key of an identity is saved in
+    /// the store. As for private keys, every front end must use this
+    /// function. The length is only hashed if it is not the default.
+    /// The labels are not hashed, as children are never saved: all
+    /// the children of a key share the store key of their root.
+    pub fn identity_store_key<T : IsSymmetricKeyIdentity>(key_identity: &T, mut hasher: StoreHasher) -> Vec<u8> {
+
+        // The NUL byte separates these keys from the ones of the
+        // private keys, whose types never contain a NUL byte.
+        hasher.update(b"symmetric\0");
+        hasher.update(key_identity.key_id().as_bytes());
+
+        match key_length(key_identity) {
+            Ok(length) if length != K_DEFAULT_LENGTH as usize => {
+                hasher.update(b"\0");
+                hasher.update(format!("length={length}").as_bytes());
+            },
+            _ => ()
+        }
+
+        Vec::from(hasher.finish())
+    }
+}
+
+/// The salt of the HKDF used to derive keys from a symmetric key.
+const K_DERIVATION_SALT : &[u8] = b"cryptonix symmetric key derivation v1";
+
+/// The prefix of the HKDF info used to derive child keys. The
+/// label of the child follows the NUL byte.
+const K_DERIVATION_INFO : &[u8] = b"cryptonix child key\0";
+
+/// The prefix of the HKDF info used to derive the key of an AEAD
+/// algorithm. The name of the algorithm follows the NUL byte.
+const K_AEAD_KEY_INFO : &[u8] = b"cryptonix aead key\0";
+
+/// The length of the authentication tag of every AEAD algorithm.
+const K_AEAD_TAG_LENGTH : usize = 16;
+
+/// A symmetric secret key, which consists of random bytes. The key
+/// never leaves CryptoNix, only values derived from it do.
+#[derive(Clone)]
+pub struct SymmetricKey {
+    bytes: Vec<u8>
+}
+
+impl SymmetricKey {
+
+    pub fn new(length: usize) -> Result<SymmetricKey, Error> {
+        let mut bytes = vec![0; length];
+        rand_bytes(&mut bytes)?;
+        Ok(SymmetricKey { bytes })
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> SymmetricKey {
+        SymmetricKey { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Derive the child key with the given label using HKDF-SHA256
+    /// (RFC 5869). The child has the same length as this key. The
+    /// salt and the info are specific to this derivation, which
+    /// separates it from any other use of the key, including the
+    /// hashing of the store keys with 'StoreHasher'.
+    pub fn derive_child(&self, label: &str) -> Result<SymmetricKey, Error> {
+
+        if label.is_empty() {
+            return Err(Error::invalid_argument("The labels of a derived symmetric key cannot be empty.".to_string()));
+        }
+
+        let prk = hkdf::extract(Md::sha256(), K_DERIVATION_SALT, &self.bytes)?;
+        let info = [K_DERIVATION_INFO, label.as_bytes()].concat();
+        Ok(SymmetricKey { bytes: hkdf::expand(Md::sha256(), &prk, &info, self.bytes.len())? })
+    }
+
+    /// Derive the key of an AEAD algorithm from this key. Each
+    /// algorithm uses its own key, which also separates encryption
+    /// from the other uses of the key, such as HMAC.
+    fn aead_key(&self, algorithm: AeadAlgorithm) -> Result<Vec<u8>, Error> {
+
+        let prk = hkdf::extract(Md::sha256(), K_DERIVATION_SALT, &self.bytes)?;
+        let info = [K_AEAD_KEY_INFO, algorithm.name().as_bytes()].concat();
+        hkdf::expand(Md::sha256(), &prk, &info, algorithm.key_length())
+    }
+
+    /// Encrypt 'plaintext' with a deterministic AEAD algorithm: the
+    /// same plaintext and associated data always give the same
+    /// ciphertext, which only reveals whether two plaintexts are
+    /// equal. The layout of the result is the one of the RFC of the
+    /// algorithm: 'tag || ciphertext' for AES-SIV (RFC 5297) and
+    /// 'ciphertext || tag' for AES-GCM-SIV (RFC 8452).
+    pub fn aead_encrypt(
+        &self,
+        algorithm: AeadAlgorithm,
+        plaintext: &[u8],
+        associated_data: Option<&[u8]>
+    ) -> Result<Vec<u8>, Error> {
+
+        // OpenSSL only implements AES-SIV for non-empty plaintexts
+        if algorithm == AeadAlgorithm::Aes256Siv && plaintext.is_empty() {
+            return Err(Error::invalid_argument(format!("The algorithm {} cannot encrypt an empty plaintext.", algorithm.name())));
+        }
+
+        aead_seal(algorithm, &self.aead_key(algorithm)?, algorithm.nonce(), plaintext, associated_data)
+    }
+
+    /// Decrypt a ciphertext produced by 'aead_encrypt'. Any
+    /// change to the ciphertext or to the associated data is
+    /// reported as the same error, whatever its cause.
+    pub fn aead_decrypt(
+        &self,
+        algorithm: AeadAlgorithm,
+        ciphertext: &[u8],
+        associated_data: Option<&[u8]>
+    ) -> Result<Vec<u8>, Error> {
+
+        aead_open(algorithm, &self.aead_key(algorithm)?, algorithm.nonce(), ciphertext, associated_data)
+    }
+
+    /// Compute 'HMAC(key, message)' (RFC 2104).
+    pub fn hmac(&self, digest: HmacDigest, message: &[u8]) -> Result<Vec<u8>, Error> {
+
+        let key = PKey::hmac(&self.bytes)?;
+        let mut signer = Signer::new(digest.message_digest(), &key)?;
+        Ok(signer.sign_oneshot_to_vec(message)?)
+    }
+}
+
+/// Encrypt 'plaintext' with the raw 'key' of the AEAD algorithm,
+/// laying out the result as described in 'aead_encrypt'.
+fn aead_seal(
+    algorithm: AeadAlgorithm,
+    key: &[u8],
+    nonce: Option<&[u8]>,
+    plaintext: &[u8],
+    associated_data: Option<&[u8]>
+) -> Result<Vec<u8>, Error> {
+
+    let cipher = algorithm.cipher()?;
+    let mut ctx = CipherCtx::new()?;
+
+    ctx.encrypt_init(Some(&cipher), Some(key), nonce)?;
+    if let Some(associated_data) = associated_data {
+        ctx.cipher_update(associated_data, None)?;
+    }
+
+    let mut ciphertext = Vec::new();
+    ctx.cipher_update_vec(plaintext, &mut ciphertext)?;
+    ctx.cipher_final_vec(&mut ciphertext)?;
+
+    let mut tag = [0; K_AEAD_TAG_LENGTH];
+    ctx.tag(&mut tag)?;
+
+    Ok(match algorithm {
+        AeadAlgorithm::Aes256Siv => [&tag[..], &ciphertext].concat(),
+        AeadAlgorithm::Aes256GcmSiv => [&ciphertext[..], &tag].concat()
+    })
+}
+
+/// Decrypt a ciphertext produced by 'aead_seal'.
+fn aead_open(
+    algorithm: AeadAlgorithm,
+    key: &[u8],
+    nonce: Option<&[u8]>,
+    ciphertext: &[u8],
+    associated_data: Option<&[u8]>
+) -> Result<Vec<u8>, Error> {
+
+    let invalid = || Error::invalid_argument(format!(
+        "The ciphertext could not be decrypted with {}. Either the key, the associated data or the ciphertext is wrong.",
+        algorithm.name()
+    ));
+
+    if ciphertext.len() < K_AEAD_TAG_LENGTH {
+        return Err(invalid());
+    }
+
+    let (tag, ciphertext) = match algorithm {
+        AeadAlgorithm::Aes256Siv => {
+            let (tag, ciphertext) = ciphertext.split_at(K_AEAD_TAG_LENGTH);
+            (tag, ciphertext)
+        },
+        AeadAlgorithm::Aes256GcmSiv => {
+            let (ciphertext, tag) = ciphertext.split_at(ciphertext.len() - K_AEAD_TAG_LENGTH);
+            (tag, ciphertext)
+        }
+    };
+
+    let cipher = algorithm.cipher()?;
+    let mut ctx = CipherCtx::new()?;
+
+    ctx.decrypt_init(Some(&cipher), Some(key), nonce)?;
+    ctx.set_tag(tag)?;
+
+    let mut plaintext = Vec::new();
+    let result = associated_data
+        .map_or(Ok(0), |associated_data| ctx.cipher_update(associated_data, None))
+        .and_then(|_| ctx.cipher_update_vec(ciphertext, &mut plaintext))
+        .and_then(|_| ctx.cipher_final_vec(&mut plaintext));
+
+    match result {
+        Ok(_) => Ok(plaintext),
+        Err(_) => Err(invalid())
+    }
+}
+
+/// The hash functions with which a HMAC can be computed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HmacDigest {
+    /// Only for services which still require it, such as
+    /// legacy webhook signatures.
+    Sha1,
+    #[default]
+    Sha256,
+    Sha384,
+    Sha512
+}
+
+/// The names used to refer to each hash function from Nix.
+const K_HMAC_DIGESTS : &[(&str, HmacDigest)] = &[
+    ("sha1", HmacDigest::Sha1),
+    ("sha256", HmacDigest::Sha256),
+    ("sha384", HmacDigest::Sha384),
+    ("sha512", HmacDigest::Sha512)
+];
+
+impl HmacDigest {
+
+    fn message_digest(&self) -> MessageDigest {
+
+        match self {
+            HmacDigest::Sha1 => MessageDigest::sha1(),
+            HmacDigest::Sha256 => MessageDigest::sha256(),
+            HmacDigest::Sha384 => MessageDigest::sha384(),
+            HmacDigest::Sha512 => MessageDigest::sha512()
+        }
+    }
+}
+
+impl TryFrom<&str> for HmacDigest {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<HmacDigest, Error> {
+
+        K_HMAC_DIGESTS.iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, digest)| *digest)
+            .ok_or_else(|| {
+                let names: Vec<&str> = K_HMAC_DIGESTS.iter().map(|(name, _)| *name).collect();
+                Error::invalid_argument(format!(
+                    "The value '{value}' is not a known HMAC hash function. The supported hash functions are: {}.",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// The deterministic AEAD algorithms. Both resist the reuse of
+/// nonces, hence they can be used without a random nonce.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AeadAlgorithm {
+    /// AES-SIV (RFC 5297) with two 256 bit AES keys.
+    #[default]
+    Aes256Siv,
+    /// AES-GCM-SIV (RFC 8452) with a 256 bit key and a nonce of
+    /// zeros. Requires OpenSSL 3.2 or later.
+    Aes256GcmSiv
+}
+
+/// The names used to refer to each AEAD algorithm from Nix.
+const K_AEAD_ALGORITHMS : &[(&str, AeadAlgorithm)] = &[
+    ("aes-256-siv", AeadAlgorithm::Aes256Siv),
+    ("aes-256-gcm-siv", AeadAlgorithm::Aes256GcmSiv)
+];
+
+/// The nonce of AES-GCM-SIV. A fixed nonce makes the encryption
+/// deterministic, which AES-GCM-SIV is designed to withstand.
+const K_GCM_SIV_NONCE : &[u8] = &[0; 12];
+
+impl AeadAlgorithm {
+
+    pub fn name(&self) -> &'static str {
+        K_AEAD_ALGORITHMS.iter()
+            .find(|(_, algorithm)| algorithm == self)
+            .map(|(name, _)| *name)
+            .unwrap_or("unknown")
+    }
+
+    fn key_length(&self) -> usize {
+
+        match self {
+            AeadAlgorithm::Aes256Siv => 64,
+            AeadAlgorithm::Aes256GcmSiv => 32
+        }
+    }
+
+    fn nonce(&self) -> Option<&'static [u8]> {
+
+        match self {
+            AeadAlgorithm::Aes256Siv => None,
+            AeadAlgorithm::Aes256GcmSiv => Some(K_GCM_SIV_NONCE)
+        }
+    }
+
+    fn cipher(&self) -> Result<Cipher, Error> {
+
+        let openssl_name = match self {
+            AeadAlgorithm::Aes256Siv => "AES-256-SIV",
+            AeadAlgorithm::Aes256GcmSiv => "AES-256-GCM-SIV"
+        };
+
+        Cipher::fetch(None, openssl_name, None)
+            .map_err(|e| Error::configuration(format!(
+                "The AEAD algorithm {} is not available in this build of OpenSSL: {e}",
+                self.name()
+            )))
+    }
+}
+
+impl TryFrom<&str> for AeadAlgorithm {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<AeadAlgorithm, Error> {
+
+        K_AEAD_ALGORITHMS.iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, algorithm)| *algorithm)
+            .ok_or_else(|| {
+                let names: Vec<&str> = K_AEAD_ALGORITHMS.iter().map(|(name, _)| *name).collect();
+                Error::invalid_argument(format!(
+                    "The value '{value}' is not a known AEAD algorithm. The supported algorithms are: {}.",
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+/// An identity of a symmetric key built in Rust, such as the
+/// ones supplied on the command line.
+pub struct SymmetricKeyIdentity {
+    pub key_id: String,
+    pub length: Option<u32>,
+    pub labels: Vec<String>
+}
+
+impl IsCryptoStoreKey for SymmetricKeyIdentity {
+    type Value = SymmetricKey;
+
+    fn to_store_key_raw(&self, hasher: StoreHasher) -> Vec<u8> {
+        ffi::identity_store_key(self, hasher)
+    }
+
+    fn to_store_value_raw(value: &SymmetricKey) -> Result<Vec<u8>, Error> {
+        Ok(value.as_bytes().to_vec())
+    }
+
+    fn from_store_value_raw(bytes: &Vec<u8>) -> Result<SymmetricKey, Error> {
+        Ok(SymmetricKey::from_bytes(bytes.clone()))
+    }
+}
+
+impl ffi::IsSymmetricKeyIdentity for SymmetricKeyIdentity {
+
+    fn key_id(&self) -> &String {
+        &self.key_id
+    }
+
+    fn length(&self) -> Result<Option<u32>, Error> {
+        Ok(self.length)
+    }
+
+    fn labels(&self) -> &Vec<String> {
+        &self.labels
+    }
+}
+
+impl CryptoNix {
+
+    /// Get the symmetric key of the given identity. If there is no
+    /// key associated with the identity, a fresh key is generated
+    /// and saved to the store. If the identity has labels, the child
+    /// derived from the stored key is returned instead. Children are
+    /// derived deterministically, hence they are not saved.
+    pub fn symmetric_key<T : ffi::IsSymmetricKeyIdentity>(
+        &self,
+        key_identity: &T
+    ) -> Result<SymmetricKey, Error> {
+
+        let root = self.symmetric_root_key(key_identity, true)?;
+        Self::derive_symmetric_key(key_identity, root)
+    }
+
+    /// Get the symmetric key of the given identity, which must already
+    /// be in the store. Used to undo what was done with the key, hence
+    /// generating a new key would be pointless.
+    pub fn existing_symmetric_key<T : ffi::IsSymmetricKeyIdentity>(
+        &self,
+        key_identity: &T
+    ) -> Result<SymmetricKey, Error> {
+
+        let root = self.symmetric_root_key(key_identity, false)?;
+        Self::derive_symmetric_key(key_identity, root)
+    }
+
+    fn derive_symmetric_key<T : ffi::IsSymmetricKeyIdentity>(
+        key_identity: &T,
+        root: SymmetricKey
+    ) -> Result<SymmetricKey, Error> {
+
+        key_identity.labels().iter().try_fold(root, |key, label| {
+            key.derive_child(label).with_context(|| format!(
+                "while deriving the child '{}' of the symmetric key identity '{}'",
+                label,
+                key_identity.key_id()
+            ))
+        })
+    }
+
+    fn symmetric_root_key<T : ffi::IsSymmetricKeyIdentity>(
+        &self,
+        key_identity: &T,
+        generate: bool
+    ) -> Result<SymmetricKey, Error> {
+
+        let describe_identity = || format!("the symmetric key identity '{}'", key_identity.key_id());
+
+        let length = ffi::key_length(key_identity)
+            .with_context(|| format!("while reading the parameters of {}", describe_identity()))?;
+
+        let check_length = |key: SymmetricKey| {
+            if key.as_bytes().len() == length {
+                Ok(key)
+            } else {
+                Err(Error::invalid_argument(format!(
+                    "The key of {} has {} bytes, but the identity expects {length}.",
+                    describe_identity(),
+                    key.as_bytes().len()
+                )))
+            }
+        };
+
+        let generate_key = || {
+            if !generate {
+                return Err(Error::invalid_argument(format!("There is no symmetric key for {} in the store.", describe_identity())));
+            }
+            SymmetricKey::new(length)
+                .with_context(|| format!("while generating a new key for {}", describe_identity()))
+        };
+
+        self.get_or_insert_with(
+            key_identity,
+            || format!("the key of {}", describe_identity()),
+            generate_key,
+            check_length
+        )
+    }
+
+    /// Compute 'HMAC(key, message)' with the symmetric key of the given
+    /// identity. As the key is never revealed, the result can be used
+    /// as a stable identifier or as the expected signature of a message.
+    pub fn symmetric_hmac<T : ffi::IsSymmetricKeyIdentity>(
+        &self,
+        key_identity: &T,
+        digest: HmacDigest,
+        message: &[u8]
+    ) -> Result<Vec<u8>, Error> {
+
+        self.symmetric_key(key_identity)?.hmac(digest, message)
+            .with_context(|| format!("while computing a HMAC with the symmetric key identity '{}'", key_identity.key_id()))
+    }
+
+    /// Encrypt 'plaintext' with the symmetric key of the given identity.
+    /// The encryption is deterministic, so that values built from the
+    /// ciphertext, such as derivations, are reproducible.
+    pub fn symmetric_encrypt<T : ffi::IsSymmetricKeyIdentity>(
+        &self,
+        key_identity: &T,
+        algorithm: AeadAlgorithm,
+        plaintext: &[u8],
+        associated_data: Option<&[u8]>
+    ) -> Result<Vec<u8>, Error> {
+
+        self.symmetric_key(key_identity)?.aead_encrypt(algorithm, plaintext, associated_data)
+            .with_context(|| format!("while encrypting with the symmetric key identity '{}'", key_identity.key_id()))
+    }
+
+    /// Decrypt a ciphertext produced by 'symmetric_encrypt'. The key
+    /// must already be in the store. This is deliberately not exposed
+    /// to Nix, whose values may end up in the world readable Nix store.
+    pub fn symmetric_decrypt<T : ffi::IsSymmetricKeyIdentity>(
+        &self,
+        key_identity: &T,
+        algorithm: AeadAlgorithm,
+        ciphertext: &[u8],
+        associated_data: Option<&[u8]>
+    ) -> Result<Vec<u8>, Error> {
+
+        self.existing_symmetric_key(key_identity)?.aead_decrypt(algorithm, ciphertext, associated_data)
+            .with_context(|| format!("while decrypting with the symmetric key identity '{}'", key_identity.key_id()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::encoding;
+    use crate::testing::{TempStore};
+
+    fn hex(value: &str) -> Vec<u8> {
+        encoding::from_hex(value).unwrap()
+    }
+
+    fn identity(key_id: &str, length: Option<u32>) -> SymmetricKeyIdentity {
+        SymmetricKeyIdentity {
+            key_id: key_id.to_string(),
+            length,
+            labels: Vec::new()
+        }
+    }
+
+    /// Whether AES-256-GCM-SIV is available, which requires OpenSSL 3.2
+    /// or later. The tests of AES-256-GCM-SIV are skipped otherwise.
+    fn gcm_siv_available() -> bool {
+        let available = AeadAlgorithm::Aes256GcmSiv.cipher().is_ok();
+        if !available {
+            eprintln!("Skipping AES-256-GCM-SIV, which needs OpenSSL 3.2 or later.");
+        }
+        available
+    }
+
+    /// RFC 5297 only has vectors for AES-SIV with 128 bit AES keys. These
+    /// vectors with 256 bit AES keys were produced with the 'AESSIV' class
+    /// of the Python 'cryptography' package.
+    #[test]
+    fn matches_aes_256_siv_vectors() {
+        let key: Vec<u8> = (0..64).collect();
+
+        for (associated_data, expected) in [
+            (None, "723ee26ba468f85d0e0b00e5ebd6966b41dd2b7ed20b06b6dd"),
+            (Some(&b"app.conf"[..]), "12848d46a340767b54d5999b0d7d935d0c52df5a4787d27c65")
+        ] {
+            let ciphertext = aead_seal(AeadAlgorithm::Aes256Siv, &key, None, b"api token", associated_data).unwrap();
+            assert_eq!(ciphertext, hex(expected));
+            assert_eq!(aead_open(AeadAlgorithm::Aes256Siv, &key, None, &ciphertext, associated_data).unwrap(), b"api token");
+        }
+    }
+
+    #[test]
+    fn matches_rfc_8452_vectors() {
+        if !gcm_siv_available() {
+            return;
+        }
+
+        // RFC 8452, appendix C.2
+        let key = hex("0100000000000000000000000000000000000000000000000000000000000000");
+        let nonce = hex("030000000000000000000000");
+
+        for (plaintext, associated_data, expected) in [
+            ("", "", "07f5f4169bbf55a8400cd47ea6fd400f"),
+            ("0100000000000000", "", "c2ef328e5c71c83b843122130f7364b761e0b97427e3df28"),
+            ("0200000000000000", "01", "1de22967237a813291213f267e3b452f02d01ae33e4ec854")
+        ] {
+            let associated_data = hex(associated_data);
+            let associated_data = Some(&associated_data[..]).filter(|data| !data.is_empty());
+
+            let ciphertext = aead_seal(AeadAlgorithm::Aes256GcmSiv, &key, Some(&nonce), &hex(plaintext), associated_data).unwrap();
+            assert_eq!(ciphertext, hex(expected));
+            assert_eq!(aead_open(AeadAlgorithm::Aes256GcmSiv, &key, Some(&nonce), &ciphertext, associated_data).unwrap(), hex(plaintext));
+        }
+    }
+
+    #[test]
+    fn decrypts_only_with_the_same_key_and_associated_data() {
+        let key = SymmetricKey::from_bytes(vec![1; 32]);
+        let other_key = SymmetricKey::from_bytes(vec![2; 32]);
+
+        let mut algorithms = vec![AeadAlgorithm::Aes256Siv];
+        if gcm_siv_available() {
+            algorithms.push(AeadAlgorithm::Aes256GcmSiv);
+        }
+
+        for algorithm in algorithms {
+            let ciphertext = key.aead_encrypt(algorithm, b"api token", Some(b"app.conf")).unwrap();
+            assert_eq!(ciphertext, key.aead_encrypt(algorithm, b"api token", Some(b"app.conf")).unwrap());
+            assert_eq!(key.aead_decrypt(algorithm, &ciphertext, Some(b"app.conf")).unwrap(), b"api token");
+
+            let mut tampered = ciphertext.clone();
+            tampered[0] ^= 1;
+
+            for result in [
+                key.aead_decrypt(algorithm, &tampered, Some(b"app.conf")),
+                key.aead_decrypt(algorithm, &ciphertext, Some(b"other.conf")),
+                key.aead_decrypt(algorithm, &ciphertext, None),
+                key.aead_decrypt(algorithm, &ciphertext[..8], Some(b"app.conf")),
+                other_key.aead_decrypt(algorithm, &ciphertext, Some(b"app.conf"))
+            ] {
+                assert!(result.is_err(), "{algorithm:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn matches_hmac_vectors() {
+        // RFC 2202, test case 1, and RFC 4231, test cases 1 and 2
+        let hi_there = (hex("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b"), &b"Hi There"[..]);
+        let jefe = (b"Jefe".to_vec(), &b"what do ya want for nothing?"[..]);
+
+        for ((key, message), digest, expected) in [
+            (&hi_there, HmacDigest::Sha1, "b617318655057264e28bc0b6fb378c8ef146be00"),
+            (&hi_there, HmacDigest::Sha256, "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"),
+            (&hi_there, HmacDigest::Sha384, "afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6"),
+            (&hi_there, HmacDigest::Sha512, "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"),
+            (&jefe, HmacDigest::Sha256, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"),
+            (&jefe, HmacDigest::Sha384, "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e8e2240ca5e69e2c78b3239ecfab21649"),
+            (&jefe, HmacDigest::Sha512, "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737")
+        ] {
+            let key = SymmetricKey::from_bytes(key.clone());
+            assert_eq!(key.hmac(digest, message).unwrap(), hex(expected), "{digest:?}");
+        }
+    }
+
+    #[test]
+    fn keys_are_stable_across_reopens() {
+        let store = TempStore::new();
+
+        let key = store.open().symmetric_key(&identity("name=app", None)).unwrap();
+        assert_eq!(key.as_bytes().len(), 32);
+
+        let crypto_nix = store.open();
+        assert_eq!(crypto_nix.symmetric_key(&identity("name=app", None)).unwrap().as_bytes(), key.as_bytes());
+        assert_eq!(crypto_nix.existing_symmetric_key(&identity("name=app", None)).unwrap().as_bytes(), key.as_bytes());
+
+        let longer = crypto_nix.symmetric_key(&identity("name=app", Some(64))).unwrap();
+        assert_eq!(longer.as_bytes().len(), 64);
+        assert_ne!(&longer.as_bytes()[..32], key.as_bytes());
+    }
+
+    #[test]
+    fn derives_children_by_label() {
+        let key = SymmetricKey::from_bytes((0..32).collect());
+        let derive = |labels: &[&str]| -> Vec<u8> {
+            labels.iter()
+                .try_fold(key.clone(), |key, label| key.derive_child(label))
+                .unwrap()
+                .as_bytes()
+                .to_vec()
+        };
+
+        let child = derive(&["a"]);
+        assert_eq!(child.len(), 32);
+        assert_eq!(child, derive(&["a"]));
+        assert_ne!(child, key.as_bytes());
+        assert_ne!(child, derive(&["b"]));
+        assert_ne!(child, derive(&["a", "b"]));
+        assert_ne!(derive(&["a", "b"]), derive(&["b", "a"]));
+
+        assert!(key.derive_child("").is_err());
+
+        // Children are separated from the keys used for encryption
+        for algorithm in [AeadAlgorithm::Aes256Siv, AeadAlgorithm::Aes256GcmSiv] {
+            let aead_key = key.aead_key(algorithm).unwrap();
+            assert_ne!(&child[..], &aead_key[..child.len().min(aead_key.len())]);
+        }
+    }
+}
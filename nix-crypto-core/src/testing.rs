@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::{PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::foundations::{CryptoNix};
+use crate::openssl::{PrivateKeyIdentity};
+use crate::openssl::pkey::{Key};
+
+static K_NEXT_PATH : AtomicUsize = AtomicUsize::new(0);
+
+/// A fresh path in the temporary directory, unique to this test run.
+fn temp_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "cryptonix-test-{}-{}",
+        std::process::id(),
+        K_NEXT_PATH.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// A fresh temporary directory, which is removed
+/// once the value is dropped.
+pub struct TempDir {
+    pub path: PathBuf
+}
+
+impl TempDir {
+
+    pub fn new() -> TempDir {
+        let path = temp_path();
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A store in a fresh temporary directory, which is removed
+/// once the value is dropped.
+pub struct TempStore {
+    pub path: PathBuf
+}
+
+impl TempStore {
+
+    pub fn new() -> TempStore {
+        TempStore { path: temp_path() }
+    }
+
+    /// Open 'CryptoNix' on this store with the given key pool size.
+    pub fn open_with_pool(&self, key_pool_size: usize) -> CryptoNix {
+        CryptoNix::with_args(&format!(
+            "mode=filesystem&store-path={}&key-pool-size={key_pool_size}",
+            self.path.display()
+        ))
+    }
+
+    pub fn open(&self) -> CryptoNix {
+        self.open_with_pool(0)
+    }
+}
+
+impl Drop for TempStore {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// The identity of a private key with the default parameters.
+pub fn identity(key_type: &str, key_id: &str) -> PrivateKeyIdentity {
+    PrivateKeyIdentity::new(key_type, key_id)
+}
+
+/// Check that a feature uses a key reserved to it rather than the key
+/// of 'key_identity'. 'public_key' is the public key used by the
+/// feature, and 'encode' encodes the plain key of the identity the same
+/// way. The reserved key must also be kept by the store.
+pub fn assert_reserves_the_key(
+    key_identity: &PrivateKeyIdentity,
+    public_key: impl Fn(&CryptoNix) -> String,
+    encode: impl Fn(&Key) -> String
+) {
+    let store = TempStore::new();
+    let crypto_nix = store.open();
+
+    let reserved = public_key(&crypto_nix);
+    let plain = crypto_nix.openssl_private_key(key_identity).unwrap();
+    assert_ne!(reserved, encode(&plain));
+
+    drop(crypto_nix);
+    assert_eq!(public_key(&store.open()), reserved);
+}
@@ -1,6 +1,10 @@
 #pragma once
 
+#include <functional>
+#include <map>
+
 #include <nix/expr/primops.hh>
+#include <nix/expr/value-to-json.hh>
 #include <nix/cmd/common-eval-args.hh>
 #include <nix/expr/eval-settings.hh>
 #include <nix/fetchers/filtering-source-accessor.hh>
@@ -20,15 +24,51 @@ struct ExtraBuiltinsSettings : nix::Config {
     "extra-cryptonix-args",
     "The configuration parameters for CyrptoNix."
   };
+  nix::Setting<std::string> cryptoNixMode{
+    this,
+    "",
+    "cryptonix-mode",
+    "The mode used by CryptoNix to manage private credentials. Takes precedence over the 'mode' given in 'extra-cryptonix-args'."
+  };
+  nix::Setting<std::string> cryptoNixStorePath{
+    this,
+    "",
+    "cryptonix-store-path",
+    "The location where CryptoNix stores private credentials. Takes precedence over the 'store-path' given in 'extra-cryptonix-args'."
+  };
 };
 
+// Accessor for one of the CryptoNix instances managed by the plugin.
+// Primops refer to their instance through an accessor as the default
+// instance is only initialized upon its first usage.
+using CryptoNixInstance = std::function<CxxNixCrypto&()>;
+
 class CryptoNixPrimops {
   public:
   CryptoNixPrimops();
   ~CryptoNixPrimops();
 
-  std::string opensslPublicKeyPem(OpensslPrivateKeyIdentity&& key_identity);
-  std::string opensslX509Pem(X509BuildParams&& buildParams);
+  CryptoNixInstance defaultInstance();
+  CryptoNixInstance open(const std::map<std::string, std::string>& options);
+
+  std::string opensslPublicKeyPem(CxxNixCrypto& instance, OpensslPrivateKeyIdentity&& key_identity);
+  std::string opensslPublicKey(CxxNixCrypto& instance, OpensslPrivateKeyIdentity&& key_identity, const rust::String& encoding);
+  std::string opensslX509Pem(CxxNixCrypto& instance, X509BuildParams&& buildParams);
+  std::string opensslSign(CxxNixCrypto& instance, OpensslSignParams&& signParams);
+  std::string opensslEncrypt(CxxNixCrypto& instance, OpensslEncryptParams&& encryptParams);
+  std::string hmac(CxxNixCrypto& instance, HmacParams&& hmacParams);
+  std::string symmetricEncrypt(CxxNixCrypto& instance, SymmetricEncryptParams&& encryptParams);
+  std::string jwsSign(CxxNixCrypto& instance, JwsSignParams&& signParams);
+  std::string jwtSign(CxxNixCrypto& instance, JwtSignParams&& signParams);
+  std::string jwks(CxxNixCrypto& instance, JwksParams&& jwksParams);
+  std::string minisignPublicKey(CxxNixCrypto& instance, MinisignPublicKeyParams&& keyParams);
+  std::string minisignSign(CxxNixCrypto& instance, MinisignSignParams&& signParams);
+  std::string nixCachePublicKey(CxxNixCrypto& instance, NixCacheKeyParams&& keyParams);
+  std::string nixCacheSign(CxxNixCrypto& instance, NixCacheSignParams&& signParams);
+  std::string passwordHash(CxxNixCrypto& instance, PasswordHashParams&& hashParams);
+  std::string openpgpPublicKey(CxxNixCrypto& instance, OpenpgpKeyParams&& keyParams);
+  std::string openpgpFingerprint(CxxNixCrypto& instance, OpenpgpKeyParams&& keyParams);
+  std::string openpgpSign(CxxNixCrypto& instance, OpenpgpSignParams&& signParams);
 
   private:
   //nix::RegisterPrimOp age;
@@ -47,6 +87,11 @@ class CryptoNixPrimops {
   // the first usage of cryptonix.
   std::unique_ptr<rust::Box<CxxNixCrypto>> cryptoNixPtr;
 
+  // The instances created via 'builtins.crypto.open'. Instances are
+  // cached by their options so that opening the same configuration
+  // multiple times yields the same instance.
+  std::map<std::string, rust::Box<CxxNixCrypto>> openedInstances;
+
   rust::Box<CxxNixCrypto>& cryptoNix() noexcept;
 };
 
@@ -1,19 +1,63 @@
-use cxx::{CxxString};
 use std::boxed::{Box};
 
 // Imports from sister crates
-use nix_crypto_core::error::{Error};
+use nix_crypto_core::args::{CryptoNixSettings};
+use nix_crypto_core::encoding;
+use nix_crypto_core::error::{Error, ErrorContext};
 use nix_crypto_core::foundations::{CryptoNix};
+use nix_crypto_core::jose;
+use nix_crypto_core::minisign;
+use nix_crypto_core::openpgp;
+use nix_crypto_core::password;
 use nix_crypto_core::store::{IsCryptoStoreKey, StoreHasher};
 use nix_crypto_core::openssl::ffi;
 use nix_crypto_core::openssl::pkey;
+use nix_crypto_core::openssl::signature;
+use nix_crypto_core::openssl::x509;
+use nix_crypto_core::symmetric;
 
 // Imports from this crate
 use crate::cxx_bridge::ffi::*;
 use crate::cxx_support::*;
 
-pub fn rust_add(left: u64, right: u64) -> u64 {
-    left + right
+pub fn rust_add(left: u64, right: u64) -> Result<u64, Error> {
+    catch_panics("adding two numbers", || {
+        left.checked_add(right).ok_or(
+            Error::invalid_argument(format!("The sum of {} and {} does not fit in 64 bits.", left, right))
+        )
+    })
+}
+
+/// Check a detached signature. This does not require a
+/// 'CxxNixCrypto' instance as no store is involved.
+pub fn openssl_verify(params: OpensslVerifyParams) -> Result<bool, Error> {
+
+    catch_panics("verifying a signature", || {
+        let public_key = pkey::public_key_from_pem(params.public_key.as_bytes())
+            .context("while reading the 'public-key' parameter")?;
+        let algorithm: Option<signature::SignatureAlgorithm> = optional_param(&params.algorithm, "algorithm")?;
+        let encoding: encoding::TextEncoding = optional_param(&params.encoding, "encoding")?.unwrap_or_default();
+
+        // A signature which cannot be decoded cannot be valid either
+        let Ok(signature) = encoding.decode(&params.signature) else {
+            return Ok(false);
+        };
+
+        signature::verify(&public_key, algorithm, &params.data, &signature)
+    })
+}
+
+/// Check that a certificate has been issued by the given issuer.
+pub fn openssl_x509_verify(params: X509VerifyParams) -> Result<bool, Error> {
+
+    catch_panics("verifying a certificate", || {
+        let date = params.date.try_option().context("while reading the 'date' parameter")?;
+        x509::verify_certificate(
+            params.certificate.as_bytes(),
+            params.issuer.as_bytes(),
+            date.map(|date| date.as_str())
+        )
+    })
 }
 
 /// CXX wrapper type for "CryptoNix". This is meant to expose
@@ -21,23 +65,41 @@ pub fn rust_add(left: u64, right: u64) -> u64 {
 pub struct CxxNixCrypto(CryptoNix);
 
 /// Create a 'CxxNixCrypto' instance. This function accepts
-/// a 'CxxString' with the parameters for nix-crypto. It
-/// will process the parameters as appropiate and construct
+/// the settings that were supplied to nix-crypto via Nix. It
+/// will process the settings as appropiate and construct
 /// an instance of cryptonix configured with the given parameters.
-pub fn nix_crypto_with_settings(params: &CxxString) -> Box<CxxNixCrypto> {
-
-    let utf8_error = "Unexpected error in CryptoNix. The arguments supplied are not a valid utf-8 string. Please report this bug!";
+pub fn nix_crypto_with_settings(settings: CxxCryptoNixSettings) -> Box<CxxNixCrypto> {
 
-    let nix_crypto = match params.to_str() {
-        Ok(rust_params) => {
-            CryptoNix::with_args(rust_params)
-        },
-        Err(_err) =>
-            CryptoNix::with_error(Error::CxxError(utf8_error.to_string()))
+    let settings = CryptoNixSettings {
+        extra_args: settings.extra_args,
+        mode: settings.mode,
+        store_path: settings.store_path
     };
+
+    let nix_crypto =
+        catch_panics("initializing CryptoNix", || Ok(CryptoNix::with_settings(&settings)))
+        .unwrap_or_else(CryptoNix::with_error);
+
     Box::new(CxxNixCrypto(nix_crypto))
 }
 
+/// Create a 'CxxNixCrypto' instance from the options supplied
+/// explicitly via 'builtins.crypto.open'. As opposed to
+/// 'nix_crypto_with_settings', this function fails if the options
+/// are invalid or the store cannot be opened.
+pub fn nix_crypto_open(options: Vec<CxxCryptoNixOption>) -> Result<Box<CxxNixCrypto>, Error> {
+
+    let options: Vec<(String, String)> =
+        options.into_iter()
+        .map(|option| (option.key, option.value))
+        .collect();
+
+    catch_panics("opening a CryptoNix store", || {
+        let nix_crypto = CryptoNix::open("builtins.crypto.open", &options)?;
+        Ok(Box::new(CxxNixCrypto(nix_crypto)))
+    })
+}
+
 pub struct CxxOpensslPrivateKey(nix_crypto_core::openssl::pkey::Key);
 
 impl ffi::IsOpensslPrivateKeyIdentity for OpensslPrivateKeyIdentity {
@@ -49,11 +111,126 @@ impl ffi::IsOpensslPrivateKeyIdentity for OpensslPrivateKeyIdentity {
     fn key_id(&self) -> &String {
         &self.key_id
     }
+
+    fn rsa_bits(&self) -> Result<Option<u32>, Error> {
+        Ok(self.rsa_bits.try_option().context("while reading the 'bits' parameter")?.copied())
+    }
+
+    fn rsa_exponent(&self) -> Result<Option<u32>, Error> {
+        Ok(self.rsa_exponent.try_option().context("while reading the 'exponent' parameter")?.copied())
+    }
+
+    fn ec_curve(&self) -> Result<Option<&String>, Error> {
+        self.ec_curve.try_option().context("while reading the 'curve' parameter")
+    }
 }
 
 impl CxxOpensslPrivateKey {
     pub fn public_pem(&self) -> Result<String, Error> {
-        self.0.public_pem()
+        catch_panics("encoding the public key as PEM", || self.0.public_pem())
+    }
+
+    pub fn public_key_encoded(&self, encoding: &str) -> Result<String, Error> {
+        catch_panics("encoding the public key", || {
+            let encoding = pkey::PublicKeyEncoding::try_from(encoding)
+                .context("while reading the 'encoding' parameter")?;
+            self.0.public_key_encoded(encoding)
+        })
+    }
+}
+
+/// Read an optional parameter given by its name, such as an
+/// encoding or an algorithm, and parse it into 'T'.
+fn optional_param<'a, T: TryFrom<&'a str, Error = Error>>(param: &'a Vec<String>, name: &str) -> Result<Option<T>, Error> {
+
+    param.try_option()
+        .with_context(|| format!("while reading the '{name}' parameter"))?
+        .map(|value| T::try_from(value.as_str()))
+        .transpose()
+        .with_context(|| format!("while reading the '{name}' parameter"))
+}
+
+impl openpgp::ffi::IsOpenpgpKeyParams for OpenpgpKeyParams {
+
+    type PrivateKeyIdentity = OpensslPrivateKeyIdentity;
+
+    fn primary_key_identity(&self) -> &OpensslPrivateKeyIdentity {
+        &self.primary_key_identity
+    }
+
+    fn subkey_identity(&self) -> Result<Option<&OpensslPrivateKeyIdentity>, Error> {
+        self.subkey_identity.try_option()
+            .context("while reading the 'subkey' parameter")
+    }
+
+    fn user_id(&self) -> &String {
+        &self.user_id
+    }
+
+    fn creation_time(&self) -> &String {
+        &self.creation_time
+    }
+}
+
+impl symmetric::ffi::IsSymmetricKeyIdentity for SymmetricKeyIdentity {
+
+    fn key_id(&self) -> &String {
+        &self.key_id
+    }
+
+    fn length(&self) -> Result<Option<u32>, Error> {
+        Ok(self.length.try_option().context("while reading the 'length' parameter")?.copied())
+    }
+
+    fn labels(&self) -> &Vec<String> {
+        &self.labels
+    }
+}
+
+impl IsCryptoStoreKey for SymmetricKeyIdentity {
+    type Value = symmetric::SymmetricKey;
+
+    fn to_store_key_raw(&self, hasher: StoreHasher) -> Vec<u8> {
+        symmetric::ffi::identity_store_key(self, hasher)
+    }
+
+    fn to_store_value_raw(value: &symmetric::SymmetricKey) -> Result<Vec<u8>, Error> {
+        Ok(value.as_bytes().to_vec())
+    }
+
+    fn from_store_value_raw(bytes: &Vec<u8>) -> Result<symmetric::SymmetricKey, Error> {
+        Ok(symmetric::SymmetricKey::from_bytes(bytes.clone()))
+    }
+}
+
+impl password::ffi::IsPasswordIdentity for PasswordIdentity {
+
+    fn key_id(&self) -> &String {
+        &self.key_id
+    }
+
+    fn length(&self) -> Result<Option<u32>, Error> {
+        Ok(self.length.try_option().context("while reading the 'length' parameter")?.copied())
+    }
+
+    fn alphabet(&self) -> Result<Option<&String>, Error> {
+        self.alphabet.try_option().context("while reading the 'alphabet' parameter")
+    }
+}
+
+impl IsCryptoStoreKey for PasswordIdentity {
+    type Value = password::Password;
+
+    fn to_store_key_raw(&self, hasher: StoreHasher) -> Vec<u8> {
+        password::ffi::identity_store_key(self, hasher)
+    }
+
+    fn to_store_value_raw(value: &password::Password) -> Result<Vec<u8>, Error> {
+        Ok(value.as_str().as_bytes().to_vec())
+    }
+
+    fn from_store_value_raw(bytes: &Vec<u8>) -> Result<password::Password, Error> {
+        password::Password::from_bytes(bytes.clone())
     }
 }
 
@@ -98,8 +275,8 @@ impl ffi::IsX509BuildParams for X509BuildParams {
     type KeyUsage = X509KeyUsage;
     type BasicConstraints = X509BasicConstraints;
 
-    fn subject_public_key(&self) -> Option<&String> {
-        self.subject_public_key.try_option().unwrap()
+    fn subject_public_key(&self) -> Result<Option<&String>, Error> {
+        self.subject_public_key.try_option().context("while reading the 'subject-public-key' parameter")
     }
 
     fn signing_private_key_identity(&self) -> &OpensslPrivateKeyIdentity {
@@ -126,12 +303,12 @@ impl ffi::IsX509BuildParams for X509BuildParams {
         &self.expiry_date
     }
 
-    fn extension_key_usage(&self) -> Option<&X509KeyUsage> {
-        self.extension_key_usage.try_option().unwrap()
+    fn extension_key_usage(&self) -> Result<Option<&X509KeyUsage>, Error> {
+        self.extension_key_usage.try_option().context("while reading the 'key-usage' parameter")
     }
 
-    fn extension_basic_constraints(&self) -> Option<&X509BasicConstraints> {
-        self.extension_basic_constraints.try_option().unwrap()
+    fn extension_basic_constraints(&self) -> Result<Option<&X509BasicConstraints>, Error> {
+        self.extension_basic_constraints.try_option().context("while reading the 'basic-constraints' parameter")
     }
 }
 
@@ -140,17 +317,15 @@ pub struct CxxOpensslX509Certificate(nix_crypto_core::openssl::x509::X509Certifi
 
 impl CxxOpensslX509Certificate {
     pub fn public_pem(&self) -> Result<String, Error> {
-        self.0.public_pem()
+        catch_panics("encoding the certificate as PEM", || self.0.public_pem())
     }
 }
 
 impl IsCryptoStoreKey for OpensslPrivateKeyIdentity {
     type Value = pkey::Key;
 
-    fn to_store_key_raw(&self, mut hasher: StoreHasher) -> Vec<u8> {
-        hasher.update(self.key_type.as_bytes());
-        hasher.update(self.key_id.as_bytes());
-        Vec::from(hasher.finish())
+    fn to_store_key_raw(&self, hasher: StoreHasher) -> Vec<u8> {
+        ffi::identity_store_key(self, hasher)
     }
 
     fn to_store_value_raw(value: &pkey::Key) -> Result<Vec<u8>, Error> {
@@ -166,12 +341,191 @@ impl CxxNixCrypto {
 
     pub fn cxx_openssl_private_key(self: &CxxNixCrypto, key_identity: OpensslPrivateKeyIdentity) -> Result<Box<CxxOpensslPrivateKey>, Error> {
 
-        let key = self.0.openssl_private_key(&key_identity)?;
-        Ok(Box::new(CxxOpensslPrivateKey(key)))
+        catch_panics("obtaining an openssl private key", || {
+            let key = self.0.openssl_private_key(&key_identity)?;
+            Ok(Box::new(CxxOpensslPrivateKey(key)))
+        })
+    }
+
+    pub fn cxx_openssl_sign(&self, params: OpensslSignParams) -> Result<String, Error> {
+
+        catch_panics("signing data", || {
+            let algorithm: Option<signature::SignatureAlgorithm> = optional_param(&params.algorithm, "algorithm")?;
+            let encoding: encoding::TextEncoding = optional_param(&params.encoding, "encoding")?.unwrap_or_default();
+
+            let signature = self.0.openssl_sign(&params.key_identity, algorithm, &params.data)?;
+            Ok(encoding.encode(&signature))
+        })
+    }
+
+    pub fn cxx_openssl_encrypt(&self, params: OpensslEncryptParams) -> Result<String, Error> {
+
+        catch_panics("encrypting data", || {
+            let encoding: encoding::TextEncoding = optional_param(&params.encoding, "encoding")?.unwrap_or_default();
+
+            let ciphertext = self.0.openssl_encrypt(&params.key_identity, &params.plaintext)?;
+            Ok(encoding.encode(&ciphertext))
+        })
+    }
+
+    pub fn cxx_hmac(&self, params: HmacParams) -> Result<String, Error> {
+
+        catch_panics("computing a HMAC", || {
+            let digest: symmetric::HmacDigest = optional_param(&params.digest, "hash")?.unwrap_or_default();
+            let encoding: encoding::TextEncoding = optional_param(&params.encoding, "encoding")?.unwrap_or(encoding::TextEncoding::Hex);
+
+            let mac = self.0.symmetric_hmac(&params.key_identity, digest, &params.message)?;
+            Ok(encoding.encode(&mac))
+        })
+    }
+
+    pub fn cxx_symmetric_encrypt(&self, params: SymmetricEncryptParams) -> Result<String, Error> {
+
+        catch_panics("encrypting data with a symmetric key", || {
+            let algorithm: symmetric::AeadAlgorithm = optional_param(&params.algorithm, "algorithm")?.unwrap_or_default();
+            let associated_data = params.associated_data.try_option()
+                .context("while reading the 'associated-data' parameter")?;
+            let encoding: encoding::TextEncoding = optional_param(&params.encoding, "encoding")?.unwrap_or_default();
+
+            let ciphertext = self.0.symmetric_encrypt(
+                &params.key_identity,
+                algorithm,
+                &params.plaintext,
+                associated_data.map(|associated_data| associated_data.as_bytes())
+            )?;
+            Ok(encoding.encode(&ciphertext))
+        })
+    }
+
+    pub fn cxx_jws_sign(&self, params: JwsSignParams) -> Result<String, Error> {
+
+        catch_panics("signing a JWS", || {
+            let algorithm = optional_param(&params.algorithm, "algorithm")?;
+            let header = jose::JwsHeader {
+                key_id: params.key_id.try_option()
+                    .context("while reading the 'key-id' parameter")?
+                    .cloned(),
+                media_type: params.media_type.try_option()
+                    .context("while reading the 'type' parameter")?
+                    .cloned()
+            };
+
+            self.0.jws_sign(&params.key_identity, algorithm, &header, &params.payload)
+        })
+    }
+
+    pub fn cxx_jwt_sign(&self, params: JwtSignParams) -> Result<String, Error> {
+
+        catch_panics("signing a JWT", || {
+            let algorithm = optional_param(&params.algorithm, "algorithm")?;
+            let claim = |value: &Vec<String>, name: &str| value.try_option()
+                .with_context(|| format!("while reading the '{name}' parameter"))
+                .map(|value| value.cloned());
+
+            let claims = jose::JwtClaims {
+                issuer: claim(&params.issuer, "issuer")?,
+                subject: claim(&params.subject, "subject")?,
+                audience: claim(&params.audience, "audience")?,
+                issued_at: claim(&params.issued_at, "issued-at")?,
+                not_before: claim(&params.not_before, "not-before")?,
+                expires_at: claim(&params.expires_at, "expires-at")?,
+                jwt_id: claim(&params.jwt_id, "jwt-id")?,
+                extra: params.claims.iter()
+                    .map(|claim| (claim.name.clone(), claim.value.clone()))
+                    .collect()
+            };
+
+            self.0.jwt_sign(&params.key_identity, algorithm, &claims)
+        })
+    }
+
+    pub fn cxx_jwks(&self, params: JwksParams) -> Result<String, Error> {
+
+        catch_panics("building a JWK set", || {
+            let keys = params.keys.iter()
+                .map(|key| Ok((&key.key_identity, optional_param(&key.algorithm, "algorithm")?)))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            self.0.jwks(keys)
+        })
+    }
+
+    pub fn cxx_minisign_public_key(&self, params: MinisignPublicKeyParams) -> Result<String, Error> {
+
+        catch_panics("reading the public key of a minisign key", || {
+            let encoding = pkey::PublicKeyEncoding::try_from(params.encoding.as_str())
+                .context("while reading the 'encoding' parameter")?;
+            self.0.minisign_public_key(&params.key_identity, encoding)
+        })
+    }
+
+    pub fn cxx_minisign_sign(&self, params: MinisignSignParams) -> Result<String, Error> {
+
+        catch_panics("signing with a minisign key", || {
+            let comment = |value: &Vec<String>, name: &str| value.try_option()
+                .with_context(|| format!("while reading the '{name}' parameter"))
+                .map(|value| value.cloned());
+
+            let comments = minisign::MinisignComments {
+                untrusted: comment(&params.untrusted_comment, "untrusted-comment")?,
+                trusted: comment(&params.trusted_comment, "trusted-comment")?
+            };
+
+            self.0.minisign_sign(&params.key_identity, &params.data, &comments)
+        })
+    }
+
+    pub fn cxx_nix_cache_public_key(&self, params: NixCacheKeyParams) -> Result<String, Error> {
+
+        catch_panics("reading the public key of a Nix binary cache key", || {
+            self.0.nix_cache_public_key(&params.key_identity, &params.name)
+        })
+    }
+
+    pub fn cxx_nix_cache_sign(&self, params: NixCacheSignParams) -> Result<String, Error> {
+
+        catch_panics("signing a store path fingerprint", || {
+            self.0.nix_cache_sign(&params.key.key_identity, &params.key.name, &params.fingerprint)
+        })
+    }
+
+    pub fn cxx_password_hash(&self, params: PasswordHashParams) -> Result<String, Error> {
+
+        catch_panics("hashing a password", || {
+            let scheme: password::PasswordScheme = optional_param(&params.scheme, "scheme")?.unwrap_or_default();
+
+            self.0.password_hash(&params.password_identity, scheme)
+        })
+    }
+
+    pub fn cxx_openpgp_public_key(&self, params: OpenpgpKeyParams) -> Result<String, Error> {
+
+        catch_panics("building an OpenPGP certificate", || {
+            self.0.openpgp_certificate(&params)
+        })
+    }
+
+    pub fn cxx_openpgp_fingerprint(&self, params: OpenpgpKeyParams) -> Result<String, Error> {
+
+        catch_panics("computing an OpenPGP fingerprint", || {
+            self.0.openpgp_fingerprint(&params)
+        })
+    }
+
+    pub fn cxx_openpgp_sign(&self, params: OpenpgpSignParams) -> Result<String, Error> {
+
+        catch_panics("signing with an OpenPGP key", || {
+            let date = params.date.try_option()
+                .context("while reading the 'date' parameter")?;
+            self.0.openpgp_sign(&params.key, &params.data, date.map(|date| date.as_str()))
+        })
     }
 
     pub fn cxx_openssl_x509_certificate(&self, args: X509BuildParams) -> Result<Box<CxxOpensslX509Certificate>, Error> {
-        let result = self.0.openssl_x509_certificate(&args)?;
-        Ok(Box::new(CxxOpensslX509Certificate(result)))
+
+        catch_panics("building an x509 certificate", || {
+            let result = self.0.openssl_x509_certificate(&args)?;
+            Ok(Box::new(CxxOpensslX509Certificate(result)))
+        })
     }
 }
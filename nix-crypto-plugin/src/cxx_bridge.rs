@@ -22,7 +22,232 @@ pub mod ffi {
     /// values.
     pub struct OpensslPrivateKeyIdentity {
         pub key_type: String,
-        pub key_id : String
+        pub key_id : String,
+        /// The optional parameters of the algorithm used to
+        /// generate the key. Together with the type and the id,
+        /// they determine which key the identity refers to.
+        pub rsa_bits: Vec<u32>,
+        pub rsa_exponent: Vec<u32>,
+        pub ec_curve: Vec<String>
+    }
+
+    /// This struct defines the identity of a symmetric key. As
+    /// for private keys, Nix code refers to symmetric keys by
+    /// their identity and never obtains the key itself.
+    pub struct SymmetricKeyIdentity {
+        pub key_id: String,
+        /// The optional length in bytes of the key. Together with
+        /// the id, it determines which key the identity refers to.
+        pub length: Vec<u32>,
+        /// The labels of the child key derived from the key of the
+        /// id and length. Empty for the key itself.
+        pub labels: Vec<String>
+    }
+
+    /// This struct defines the identity of a generated password.
+    /// Nix code only obtains the hashes of the password.
+    pub struct PasswordIdentity {
+        pub key_id: String,
+        /// The optional length and name of the alphabet of the
+        /// password. Together with the id, they determine which
+        /// password the identity refers to.
+        pub length: Vec<u32>,
+        pub alphabet: Vec<String>
+    }
+
+    /// The parameters to hash the password of 'password_identity'.
+    /// If the 'scheme' Vec is empty, yescrypt is used.
+    pub struct PasswordHashParams {
+        pub password_identity: PasswordIdentity,
+        pub scheme: Vec<String>
+    }
+
+    /// The parameters to compute a HMAC with the symmetric
+    /// key of 'key_identity'.
+    pub struct HmacParams {
+        pub key_identity: SymmetricKeyIdentity,
+        pub message: Vec<u8>,
+        /// The name of the hash function. If the Vec is
+        /// empty, SHA-256 is used.
+        pub digest: Vec<String>,
+        /// The name of the text encoding of the result. If the
+        /// Vec is empty, the result is encoded as hexadecimal digits.
+        pub encoding: Vec<String>
+    }
+
+    /// The parameters to encrypt data deterministically with the
+    /// symmetric key of 'key_identity'.
+    pub struct SymmetricEncryptParams {
+        pub key_identity: SymmetricKeyIdentity,
+        pub plaintext: Vec<u8>,
+        /// The optional data authenticated along with the plaintext,
+        /// which is needed again to decrypt the ciphertext.
+        pub associated_data: Vec<String>,
+        /// The name of the AEAD algorithm. If the Vec is
+        /// empty, AES-256-SIV is used.
+        pub algorithm: Vec<String>,
+        /// The name of the text encoding of the ciphertext. If
+        /// the Vec is empty, the ciphertext is encoded as base64.
+        pub encoding: Vec<String>
+    }
+
+    /// The parameters of a detached signature produced with
+    /// the private key of 'key_identity'.
+    pub struct OpensslSignParams {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        /// The bytes to be signed, such as the contents of a file.
+        pub data: Vec<u8>,
+        /// The name of the signature algorithm. If the Vec is empty,
+        /// the default algorithm of the key type is used.
+        pub algorithm: Vec<String>,
+        /// The name of the text encoding of the signature. If the
+        /// Vec is empty, the signature is encoded as base64.
+        pub encoding: Vec<String>
+    }
+
+    /// The parameters to encrypt data to the public key of
+    /// the private key of 'key_identity'.
+    pub struct OpensslEncryptParams {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        pub plaintext: Vec<u8>,
+        /// The name of the text encoding of the ciphertext. If
+        /// the Vec is empty, the ciphertext is encoded as base64.
+        pub encoding: Vec<String>
+    }
+
+    /// The parameters of a JWS in the compact serialization signed
+    /// with the private key of 'key_identity'.
+    pub struct JwsSignParams {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        pub payload: Vec<u8>,
+        /// The name of the JWS algorithm, such as "ES256". If the Vec
+        /// is empty, the default algorithm of the key type is used.
+        pub algorithm: Vec<String>,
+        /// The 'kid' header. If the Vec is empty, the JWK
+        /// thumbprint of the key is used.
+        pub key_id: Vec<String>,
+        /// The optional 'typ' header.
+        pub media_type: Vec<String>
+    }
+
+    /// A claim of a JWT other than the registered claims.
+    pub struct JwtClaim {
+        pub name: String,
+        /// The value of the claim, encoded as JSON.
+        pub value: String
+    }
+
+    /// The parameters of a JWT signed with the private key of
+    /// 'key_identity'. The dates are in the RFC 3339 format.
+    pub struct JwtSignParams {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        pub issuer: Vec<String>,
+        pub subject: Vec<String>,
+        pub audience: Vec<String>,
+        pub issued_at: Vec<String>,
+        pub not_before: Vec<String>,
+        pub expires_at: Vec<String>,
+        pub jwt_id: Vec<String>,
+        pub claims: Vec<JwtClaim>,
+        /// The name of the JWS algorithm. If the Vec is empty,
+        /// the default algorithm of the key type is used.
+        pub algorithm: Vec<String>
+    }
+
+    /// A key of a JWK set, with the optional name of the
+    /// JWS algorithm published for the key.
+    pub struct JwksKey {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        pub algorithm: Vec<String>
+    }
+
+    pub struct JwksParams {
+        pub keys: Vec<JwksKey>
+    }
+
+    /// The parameters to encode the public key of the minisign
+    /// key of 'key_identity', as "minisign" or "signify".
+    pub struct MinisignPublicKeyParams {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        pub encoding: String
+    }
+
+    /// The parameters of a minisign signature made with the minisign
+    /// key of 'key_identity'. Empty Vecs use the default comments.
+    pub struct MinisignSignParams {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        pub data: Vec<u8>,
+        pub untrusted_comment: Vec<String>,
+        pub trusted_comment: Vec<String>
+    }
+
+    /// A Nix binary cache key: the Ed25519 key of 'key_identity'
+    /// and the name of the key, such as "cache.example.org-1".
+    pub struct NixCacheKeyParams {
+        pub key_identity: OpensslPrivateKeyIdentity,
+        pub name: String
+    }
+
+    /// The parameters to sign the fingerprint of a store path,
+    /// as computed from the fields of its narinfo.
+    pub struct NixCacheSignParams {
+        pub key: NixCacheKeyParams,
+        pub fingerprint: String
+    }
+
+    /// An OpenPGP key made of a primary key, which certifies and
+    /// signs, and an optional encryption subkey. The creation time
+    /// is in the RFC 3339 format.
+    pub struct OpenpgpKeyParams {
+        pub primary_key_identity: OpensslPrivateKeyIdentity,
+        pub subkey_identity: Vec<OpensslPrivateKeyIdentity>,
+        pub user_id: String,
+        pub creation_time: String
+    }
+
+    /// The parameters of a detached OpenPGP signature. If the
+    /// 'date' Vec is empty, the signature is dated at the creation
+    /// time of the key.
+    pub struct OpenpgpSignParams {
+        pub key: OpenpgpKeyParams,
+        pub data: Vec<u8>,
+        pub date: Vec<String>
+    }
+
+    /// The parameters to check a detached signature. Verifying
+    /// does not involve the store, so it is a pure operation.
+    pub struct OpensslVerifyParams {
+        /// The PEM encoded public key, or a PEM encoded
+        /// certificate of the key.
+        pub public_key: String,
+        pub data: Vec<u8>,
+        /// The signature, encoded as described by 'encoding'.
+        pub signature: String,
+        /// The name of the signature algorithm. If the Vec is empty,
+        /// the default algorithm of the key type is assumed.
+        pub algorithm: Vec<String>,
+        /// The name of the text encoding of the signature. If the
+        /// Vec is empty, the signature must be encoded as base64.
+        pub encoding: Vec<String>
+    }
+
+    /// The settings supplied to CryptoNix via the Nix
+    /// configuration. Settings which have not been
+    /// provided are represented by empty strings.
+    pub struct CxxCryptoNixSettings {
+        /// The value of the "extra-cryptonix-args" setting.
+        pub extra_args: String,
+        /// The value of the "cryptonix-mode" setting.
+        pub mode: String,
+        /// The value of the "cryptonix-store-path" setting.
+        pub store_path: String
+    }
+
+    /// An option supplied explicitly to CryptoNix, for
+    /// example as an attribute of 'builtins.crypto.open'.
+    pub struct CxxCryptoNixOption {
+        pub key: String,
+        pub value: String
     }
 
     pub struct X509NameItem {
@@ -99,21 +324,69 @@ pub mod ffi {
         pub extension_basic_constraints: Vec<X509BasicConstraints>
     }
 
+    /// The parameters to check that a certificate has been
+    /// issued by the given issuer.
+    pub struct X509VerifyParams {
+        /// The PEM encoded certificate to be checked.
+        pub certificate: String,
+        /// The PEM encoded public key or certificate of the issuer.
+        pub issuer: String,
+        /// An optional date in the "RFC3339" format at which the
+        /// certificate must be valid. There is no default as "now"
+        /// cannot be used to mantain the function pure.
+        pub date: Vec<String>
+    }
+
     extern "Rust" {
 
         type CxxNixCrypto;
         type CxxOpensslPrivateKey;
         type CxxOpensslX509Certificate;
 
-        fn nix_crypto_with_settings(settings: &CxxString) -> Box<CxxNixCrypto>;
-        fn rust_add(left: u64, right: u64) -> u64;
+        fn nix_crypto_with_settings(settings: CxxCryptoNixSettings) -> Box<CxxNixCrypto>;
+        fn nix_crypto_open(options: Vec<CxxCryptoNixOption>) -> Result<Box<CxxNixCrypto>>;
+        fn rust_add(left: u64, right: u64) -> Result<u64>;
+        fn openssl_verify(params: OpensslVerifyParams) -> Result<bool>;
+        fn openssl_x509_verify(params: X509VerifyParams) -> Result<bool>;
 
         fn cxx_openssl_private_key(self: &CxxNixCrypto, key_identity: OpensslPrivateKeyIdentity) -> Result<Box<CxxOpensslPrivateKey>>;
 
         fn cxx_openssl_x509_certificate(self: &CxxNixCrypto, args: X509BuildParams) -> Result<Box<CxxOpensslX509Certificate>>;
 
+        fn cxx_openssl_sign(self: &CxxNixCrypto, params: OpensslSignParams) -> Result<String>;
+
+        fn cxx_openssl_encrypt(self: &CxxNixCrypto, params: OpensslEncryptParams) -> Result<String>;
+
+        fn cxx_hmac(self: &CxxNixCrypto, params: HmacParams) -> Result<String>;
+
+        fn cxx_symmetric_encrypt(self: &CxxNixCrypto, params: SymmetricEncryptParams) -> Result<String>;
+
+        fn cxx_jws_sign(self: &CxxNixCrypto, params: JwsSignParams) -> Result<String>;
+
+        fn cxx_jwt_sign(self: &CxxNixCrypto, params: JwtSignParams) -> Result<String>;
+
+        fn cxx_jwks(self: &CxxNixCrypto, params: JwksParams) -> Result<String>;
+
+        fn cxx_minisign_public_key(self: &CxxNixCrypto, params: MinisignPublicKeyParams) -> Result<String>;
+
+        fn cxx_minisign_sign(self: &CxxNixCrypto, params: MinisignSignParams) -> Result<String>;
+
+        fn cxx_nix_cache_public_key(self: &CxxNixCrypto, params: NixCacheKeyParams) -> Result<String>;
+
+        fn cxx_nix_cache_sign(self: &CxxNixCrypto, params: NixCacheSignParams) -> Result<String>;
+
+        fn cxx_password_hash(self: &CxxNixCrypto, params: PasswordHashParams) -> Result<String>;
+
+        fn cxx_openpgp_public_key(self: &CxxNixCrypto, params: OpenpgpKeyParams) -> Result<String>;
+
+        fn cxx_openpgp_fingerprint(self: &CxxNixCrypto, params: OpenpgpKeyParams) -> Result<String>;
+
+        fn cxx_openpgp_sign(self: &CxxNixCrypto, params: OpenpgpSignParams) -> Result<String>;
+
         fn public_pem(self: &CxxOpensslPrivateKey) -> Result<String>;
 
+        fn public_key_encoded(self: &CxxOpensslPrivateKey, encoding: &str) -> Result<String>;
+
         fn public_pem(self: &CxxOpensslX509Certificate) -> Result<String>;
     }
 
@@ -1,4 +1,6 @@
 use core::option::{Option};
+use std::any::{Any};
+use std::panic::{self, AssertUnwindSafe};
 
 use nix_crypto_core::error::*;
 
@@ -29,3 +31,31 @@ impl<'a, T> CxxTryOption<&'a T> for &'a Vec<T> {
         }
     }
 }
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "unknown panic payload"
+    }
+}
+
+/// Run 'operation', converting any panic into an 'Error'. Every
+/// function exposed to C++ must be wrapped with this function as
+/// the 'cxx' crate aborts the process (and with it, Nix) when a
+/// panic reaches the FFI boundary. The 'description' should state
+/// what was being done, for example "reading the private key".
+pub fn catch_panics<T, F>(description: &str, operation: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>
+{
+    match panic::catch_unwind(AssertUnwindSafe(operation)) {
+        Ok(result) => result,
+        Err(payload) => Err(Error::internal(
+            format!("A panic occurred while {}: {}.", description, panic_message(payload.as_ref()))
+        ))
+    }
+}
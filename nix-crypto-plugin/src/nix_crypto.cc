@@ -1,4 +1,6 @@
 #include <format>
+#include <limits>
+#include <stdexcept>
 
 #include "nix_crypto_plugin/include/nix_crypto.hh"
 #include "nix_crypto_plugin/src/cxx_bridge.rs.h"
@@ -7,6 +9,110 @@ using namespace nix;
 
 static std::unique_ptr<CryptoNixPrimops> primops = std::make_unique<CryptoNixPrimops>();
 
+// Implementation of a CryptoNix primop. Implementations report failures
+// of the Rust code by throwing 'rust::Error'. Such errors are converted
+// by the wrappers created with 'mkCryptoNixPrimOp' and 'mkTryCryptoNixPrimOp'.
+using CryptoNixPrimOpFun = std::function<void(EvalState&, const PosIdx, Value**, Value&)>;
+
+// Errors from the Rust code are formatted as "[code] message". This
+// splits the text of the error into the code and the message.
+static std::pair<std::string, std::string> splitRustError(const rust::Error& e) {
+
+    std::string_view what(e.what());
+    auto codeEnd = what.find("] ");
+
+    if(what.starts_with('[') && codeEnd != std::string_view::npos) {
+        return { std::string(what.substr(1, codeEnd - 1)), std::string(what.substr(codeEnd + 2)) };
+    }
+
+    return { "cryptonix", std::string(what) };
+}
+
+// Create a primop which raises an evaluation error if the
+// CryptoNix operation fails.
+static PrimOp* mkCryptoNixPrimOp(const std::string& name, size_t arity, CryptoNixPrimOpFun fun) {
+
+    return new PrimOp {
+        .name = name,
+        .arity = arity,
+        .fun = [fun](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            try {
+                fun(state, pos, args, result);
+            } catch (rust::Error& e) {
+                state.error<EvalError>(e.what())
+                    .atPos(pos)
+                    .debugThrow();
+            }
+        }
+    };
+}
+
+constexpr const std::string K_TRY_PREFIX = "try-";
+constexpr const std::string K_TRY_SUCCESS = "success";
+constexpr const std::string K_TRY_VALUE = "value";
+constexpr const std::string K_TRY_ERROR = "error";
+constexpr const std::string K_TRY_ERROR_CODE = "code";
+constexpr const std::string K_TRY_ERROR_MESSAGE = "message";
+
+// Create a primop which, instead of raising an evaluation error, returns
+// an attribute set of the form '{ success; value; error = { code; message; }; }'.
+// If the operation succeeds, 'error' is null. Otherwise, 'value' is null
+// and 'error' contains the code and message of the CryptoNix error. Errors
+// caused by invalid arguments (e.g. a missing attribute) are still raised
+// as evaluation errors.
+static PrimOp* mkTryCryptoNixPrimOp(const std::string& name, size_t arity, CryptoNixPrimOpFun fun) {
+
+    return new PrimOp {
+        .name = K_TRY_PREFIX + name,
+        .arity = arity,
+        .fun = [fun](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+            auto attrs = state.buildBindings(3);
+            Value* value = state.allocValue();
+            Value* error = state.allocValue();
+
+            try {
+                fun(state, pos, args, *value);
+                error->mkNull();
+            } catch (rust::Error& e) {
+                auto [code, message] = splitRustError(e);
+                auto errorAttrs = state.buildBindings(2);
+                errorAttrs.alloc(state.symbols.create(K_TRY_ERROR_CODE)).mkString(code);
+                errorAttrs.alloc(state.symbols.create(K_TRY_ERROR_MESSAGE)).mkString(message);
+                error->mkAttrs(errorAttrs);
+                value->mkNull();
+            }
+
+            attrs.alloc(state.symbols.create(K_TRY_SUCCESS)).mkBool(error->type() == nNull);
+            attrs.insert(state.symbols.create(K_TRY_VALUE), value);
+            attrs.insert(state.symbols.create(K_TRY_ERROR), error);
+            result.mkAttrs(attrs);
+        }
+    };
+}
+
+// Add a CryptoNix primop named 'name' to 'attrs' together with
+// its 'try-' variant, which is created with 'mkTryCryptoNixPrimOp'.
+static void addCryptoNixPrimOps(EvalState& state, BindingsBuilder& attrs, const std::string& name, size_t arity, CryptoNixPrimOpFun fun) {
+
+    attrs.alloc(state.symbols.create(name)).mkPrimOp(mkCryptoNixPrimOp(name, arity, fun));
+    attrs.alloc(state.symbols.create(K_TRY_PREFIX + name)).mkPrimOp(mkTryCryptoNixPrimOp(name, arity, fun));
+}
+
+// Convert a string obtained from Nix into a 'rust::String'. Nix strings
+// are not guaranteed to be valid UTF-8, in which case an evaluation
+// error describing 'what' the string is gets raised at 'pos'.
+static rust::String toRustString(EvalState& state, const PosIdx pos, std::string_view value, const std::string& what) {
+
+    try {
+        return rust::String(value.data(), value.size());
+    } catch (std::invalid_argument&) {
+        state.error<EvalError>(std::format("{} must be a valid UTF-8 string", what))
+            .atPos(pos)
+            .debugThrow();
+    }
+}
+
 static void primop_add(EvalState & state, const PosIdx pos, Value ** args, Value & v) {
 
     auto x = state.forceInt(*args[0], pos, "while evaluating the devil").value;
@@ -20,15 +126,108 @@ static void primop_age(EvalState & state, const PosIdx pos, Value ** _args, Valu
     auto attrs = state.buildBindings(1);
 
     auto sExec = state.symbols.create("add");
-    attrs.alloc(sExec).mkPrimOp(new PrimOp {
-        .name = "add",
-        .arity = 2,
-        .fun = primop_add,
-    });
+    attrs.alloc(sExec).mkPrimOp(mkCryptoNixPrimOp("add", 2, primop_add));
 
     v.mkAttrs(attrs);
 }
 
+static rust::Vec<rust::String> tryGetString(EvalState& state, const PosIdx pos, const std::string& key, Value& attrs) {
+
+    auto attr = attrs.attrs()->get(state.symbols.create(key));
+
+    if(!attr || !attr->value) {
+        return {};
+    }
+
+    Value& value = *attr->value;
+    state.forceValue(value, pos);
+
+    // Nulls are treated as the attribute being absent
+    if(value.type() == nNull) {
+        return {};
+    }
+
+    auto result = state.forceString(
+        value,
+        pos,
+        std::format("while reading the value of the attribute '{}'", key)
+    );
+
+    return { toRustString(state, pos, result, std::format("the attribute '{}'", key)) };
+}
+
+// Read the attribute 'key' as a list of strings. A missing or null
+// attribute is read as the empty list.
+static rust::Vec<rust::String> tryGetStringList(EvalState& state, const PosIdx pos, const std::string& key, Value& attrs) {
+
+    auto attr = attrs.attrs()->get(state.symbols.create(key));
+
+    if(!attr || !attr->value) {
+        return {};
+    }
+
+    Value& value = *attr->value;
+    state.forceValue(value, pos);
+
+    // Nulls are treated as the attribute being absent
+    if(value.type() == nNull) {
+        return {};
+    }
+
+    state.forceList(
+        value,
+        pos,
+        std::format("while reading the value of the attribute '{}'", key)
+    );
+
+    rust::Vec<rust::String> result;
+    for(auto item : value.listView()) {
+        auto str = state.forceString(
+            *item,
+            pos,
+            std::format("while reading an item of the attribute '{}'", key)
+        );
+        result.push_back(toRustString(state, pos, str, std::format("an item of the attribute '{}'", key)));
+    }
+
+    return result;
+}
+
+static rust::Vec<uint32_t> tryGetUInt32(EvalState& state, const PosIdx pos, const std::string& key, Value& attrs) {
+
+    auto attr = attrs.attrs()->get(state.symbols.create(key));
+
+    if(!attr || !attr->value) {
+        return {};
+    }
+
+    Value& value = *attr->value;
+    state.forceValue(value, pos);
+
+    // Nulls are treated as the attribute being absent
+    if(value.type() == nNull) {
+        return {};
+    }
+
+    auto result = state.forceInt(
+        value,
+        pos,
+        std::format("while reading the value of the attribute '{}'", key)
+    ).value;
+
+    if(result < 0 || result > std::numeric_limits<uint32_t>::max()) {
+        state.error<EvalError>(
+            std::format("The attribute '{}' must be between 0 and {}, found {}", key, std::numeric_limits<uint32_t>::max(), result)
+        ).atPos(pos).debugThrow();
+    }
+
+    return { static_cast<uint32_t>(result) };
+}
+
+const std::string K_KEY_BITS = "bits";
+const std::string K_KEY_EXPONENT = "exponent";
+const std::string K_KEY_CURVE = "curve";
+
 static OpensslPrivateKeyIdentity openssl_get_private_key_identity(
     EvalState& state,
     const PosIdx pos,
@@ -57,48 +256,44 @@ static OpensslPrivateKeyIdentity openssl_get_private_key_identity(
         "while reading the 'key-identity' parameter"
     );
 
-    return { key_type.data(), key_id.data() };
+    return {
+        .key_type = toRustString(state, pos, key_type, "the 'key-type' parameter"),
+        .key_id = toRustString(state, pos, key_id, "the 'key-identity' parameter"),
+        .rsa_bits = tryGetUInt32(state, pos, K_KEY_BITS, key_args),
+        .rsa_exponent = tryGetUInt32(state, pos, K_KEY_EXPONENT, key_args),
+        .ec_curve = tryGetString(state, pos, K_KEY_CURVE, key_args)
+    };
 }
 
-static void primop_openssl_public_key_pem(EvalState& state, const PosIdx pos, Value** args, Value& result) {
+static void primop_openssl_public_key_pem(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
 
-    try {
-        auto pem = primops->opensslPublicKeyPem(
-            std::move(openssl_get_private_key_identity(state, pos, *args[0]))
-        );
-        result.mkString(pem);
-    } catch (rust::Error& e) {
-        state.error<EvalError>(e.what())
-            .atPos(pos)
-            .debugThrow();
-    }
+    auto pem = primops->opensslPublicKeyPem(
+        instance(),
+        std::move(openssl_get_private_key_identity(state, pos, *args[0]))
+    );
+    result.mkString(pem);
 }
 
-static rust::Vec<rust::String> tryGetString(EvalState& state, const PosIdx pos, const std::string& key, Value& attrs) {
-
-    auto attr = attrs.attrs()->get(state.symbols.create(key));
-
-    if(!attr || !attr->value) {
-        return {};
-    }
-
-    Value& value = *attr->value;
-    state.forceValue(value, pos);
+static void primop_openssl_public_key(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
 
-    // Nulls are treated as the attribute being absent
-    if(value.type() == nNull) {
-        return {};
-    }
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.public-key");
 
-    std::string result (
-        state.forceString(
-            value,
-            pos,
-            std::format("while reading the value of the attribute '{}'", key)
-        ).data()
+    auto encoding = state.forceStringNoCtx(
+        *state.getAttr(
+            state.symbols.create("encoding"),
+            args[0]->attrs(),
+            "in the openssl public key parameters"
+        )->value,
+        pos,
+        "while reading the 'encoding' parameter"
     );
 
-    return { rust::String(std::move(result)) };
+    auto encoded = primops->opensslPublicKey(
+        instance(),
+        std::move(openssl_get_private_key_identity(state, pos, *args[0])),
+        toRustString(state, pos, encoding, "the 'encoding' parameter")
+    );
+    result.mkString(encoded);
 }
 
 const std::string K_KEY_USAGE_CRITICAL = "critical";
@@ -221,15 +416,16 @@ static rust::Vec<X509NameItem> asX509Name(EvalState& state, const PosIdx pos, Va
     result.reserve(attrs.attrs()->size());
 
     for(auto attr : *attrs.attrs()) {
-        auto attrName = rust::String(std::string(state.symbols[attr.name]));
-        auto attrValue = rust::String(
-            state.forceString(
-                *attr.value,
-                pos,
-                std::format("expected the attributes of the 'X509 name' to be strings. The attribute '{}' is not a string.", attrName.data())
-            ).data()
+        std::string name(state.symbols[attr.name]);
+        auto value = state.forceString(
+            *attr.value,
+            pos,
+            std::format("expected the attributes of the 'X509 name' to be strings. The attribute '{}' is not a string.", name)
         );
-        result.emplace_back(attrName, attrValue);
+        result.push_back({
+            .entry_name = toRustString(state, pos, name, "the name of an 'X509 name' attribute"),
+            .entry_value = toRustString(state, pos, value, std::format("the value of the 'X509 name' attribute '{}'", name))
+        });
     }
 
     return std::move(result);
@@ -280,7 +476,7 @@ static X509BuildParams toX509Params(EvalState& state, const PosIdx pos, Value& p
             )->value
         );
 
-    int serial =
+    auto serial =
         state.forceInt(
             *state.getAttr(
                 state.symbols.create(K_SERIAL),
@@ -291,6 +487,12 @@ static X509BuildParams toX509Params(EvalState& state, const PosIdx pos, Value& p
             std::format("A 'X509' serial must be provided as an int under the attribute {}", K_SERIAL)
         ).value;
 
+    if(serial < 0 || serial > std::numeric_limits<uint32_t>::max()) {
+        state.error<EvalError>(
+            std::format("The 'X509' serial provided under the attribute '{}' must be between 0 and {}, found {}", K_SERIAL, std::numeric_limits<uint32_t>::max(), serial)
+        ).atPos(pos).debugThrow();
+    }
+
     auto startDate =
         state.forceString(
             *state.getAttr(
@@ -322,63 +524,666 @@ static X509BuildParams toX509Params(EvalState& state, const PosIdx pos, Value& p
         .signing_private_key_identity = std::move(signingKey),
         .issuer_name = std::move(issuerName),
         .subject_name = std::move(subjectName),
-        .serial = serial,
-        .start_date = rust::String(startDate),
-        .expiry_date = rust::String(expiryDate),
+        .serial = static_cast<uint32_t>(serial),
+        .start_date = toRustString(state, pos, startDate, std::format("the attribute '{}'", K_START_DATE)),
+        .expiry_date = toRustString(state, pos, expiryDate, std::format("the attribute '{}'", K_EXPIRY_DATE)),
         .extension_key_usage = std::move(keyUsage),
         .extension_basic_constraints = std::move(basicConstraints)
     };
 }
 
-static void primop_openssl_x509_pem(EvalState& state, const PosIdx pos, Value** args, Value& result) {
+static void primop_openssl_x509_pem(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
 
-    try {
-        auto pem = primops->opensslX509Pem(
-            toX509Params(state, pos, *args[0])
+    auto pem = primops->opensslX509Pem(
+        instance(),
+        toX509Params(state, pos, *args[0])
+    );
+    result.mkString(pem);
+}
+
+const std::string K_SIGN_DATA = "data";
+const std::string K_SIGN_ALGORITHM = "algorithm";
+const std::string K_SIGN_ENCODING = "encoding";
+
+// Read the bytes of the attribute 'key', such as the data which is
+// signed. The attribute is either a string, which is used as is (its
+// context is ignored), or a path, in which case the contents of the
+// file are used.
+static rust::Vec<uint8_t> getBytes(EvalState& state, const PosIdx pos, const std::string& key, Value& params) {
+
+    auto& value = *state.getAttr(
+        state.symbols.create(key),
+        params.attrs(),
+        "in the openssl parameters"
+    )->value;
+    state.forceValue(value, pos);
+
+    std::string data;
+    if(value.type() == nPath) {
+        data = value.path().readFile();
+    } else {
+        data = state.forceString(
+            value,
+            pos,
+            std::format("while reading the '{}' parameter, which must be a string or a path", key)
         );
-        result.mkString(pem);
-    } catch(rust::Error& e) {
-        state.error<EvalError>(e.what())
-            .atPos(pos)
-            .debugThrow();
     }
+
+    rust::Vec<uint8_t> result;
+    result.reserve(data.size());
+    for(char byte : data) {
+        result.push_back(static_cast<uint8_t>(byte));
+    }
+
+    return result;
 }
 
-constexpr const int OPENSSL_PRIMOPS_COUNT = 2;
-constexpr const std::string K_X509_PEM = "x509-pem";
+static void primop_openssl_sign(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
 
-static void primop_openssl(EvalState& state, const PosIdx _pos, Value** _args, Value& result) {
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.sign");
 
-    auto attrs = state.buildBindings(OPENSSL_PRIMOPS_COUNT);
+    auto signature = primops->opensslSign(
+        instance(),
+        {
+            .key_identity = openssl_get_private_key_identity(state, pos, *args[0]),
+            .data = getBytes(state, pos, K_SIGN_DATA, *args[0]),
+            .algorithm = tryGetString(state, pos, K_SIGN_ALGORITHM, *args[0]),
+            .encoding = tryGetString(state, pos, K_SIGN_ENCODING, *args[0])
+        }
+    );
+    result.mkString(signature);
+}
+
+const std::string K_ENCRYPT_PLAINTEXT = "plaintext";
+const std::string K_ENCRYPT_ENCODING = "encoding";
+
+static void primop_openssl_encrypt(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.encrypt");
+
+    auto ciphertext = primops->opensslEncrypt(
+        instance(),
+        {
+            .key_identity = openssl_get_private_key_identity(state, pos, *args[0]),
+            .plaintext = getBytes(state, pos, K_ENCRYPT_PLAINTEXT, *args[0]),
+            .encoding = tryGetString(state, pos, K_ENCRYPT_ENCODING, *args[0])
+        }
+    );
+    result.mkString(ciphertext);
+}
+
+const std::string K_SYMMETRIC_KEY_LENGTH = "length";
+const std::string K_SYMMETRIC_KEY_LABELS = "labels";
+
+static SymmetricKeyIdentity symmetric_get_key_identity(EvalState& state, const PosIdx pos, Value& key_args) {
+
+    auto key_id = state.forceStringNoCtx(
+        *state.getAttr(
+            state.symbols.create("key-identity"),
+            key_args.attrs(),
+            "in the symmetric key parameters"
+        )->value,
+        pos,
+        "while reading the 'key-identity' parameter"
+    );
+
+    return {
+        .key_id = toRustString(state, pos, key_id, "the 'key-identity' parameter"),
+        .length = tryGetUInt32(state, pos, K_SYMMETRIC_KEY_LENGTH, key_args),
+        .labels = tryGetStringList(state, pos, K_SYMMETRIC_KEY_LABELS, key_args)
+    };
+}
+
+const std::string K_HMAC_MESSAGE = "message";
+const std::string K_HMAC_HASH = "hash";
+const std::string K_HMAC_ENCODING = "encoding";
 
-    auto openssl_public_key_pem = state.symbols.create("public-key-pem");
-    attrs.alloc(openssl_public_key_pem).mkPrimOp(new PrimOp {
-        .name = "public-key-pem",
-        .arity = 1,
-        .fun = primop_openssl_public_key_pem
+static void primop_hmac(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.hmac");
+
+    auto mac = primops->hmac(
+        instance(),
+        {
+            .key_identity = symmetric_get_key_identity(state, pos, *args[0]),
+            .message = getBytes(state, pos, K_HMAC_MESSAGE, *args[0]),
+            .digest = tryGetString(state, pos, K_HMAC_HASH, *args[0]),
+            .encoding = tryGetString(state, pos, K_HMAC_ENCODING, *args[0])
+        }
+    );
+    result.mkString(mac);
+}
+
+const std::string K_SYMMETRIC_ENCRYPT_PLAINTEXT = "plaintext";
+const std::string K_SYMMETRIC_ENCRYPT_ASSOCIATED_DATA = "associated-data";
+const std::string K_SYMMETRIC_ENCRYPT_ALGORITHM = "algorithm";
+const std::string K_SYMMETRIC_ENCRYPT_ENCODING = "encoding";
+
+static void primop_symmetric_encrypt(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.symmetric-encrypt");
+
+    auto ciphertext = primops->symmetricEncrypt(
+        instance(),
+        {
+            .key_identity = symmetric_get_key_identity(state, pos, *args[0]),
+            .plaintext = getBytes(state, pos, K_SYMMETRIC_ENCRYPT_PLAINTEXT, *args[0]),
+            .associated_data = tryGetString(state, pos, K_SYMMETRIC_ENCRYPT_ASSOCIATED_DATA, *args[0]),
+            .algorithm = tryGetString(state, pos, K_SYMMETRIC_ENCRYPT_ALGORITHM, *args[0]),
+            .encoding = tryGetString(state, pos, K_SYMMETRIC_ENCRYPT_ENCODING, *args[0])
+        }
+    );
+    result.mkString(ciphertext);
+}
+
+const std::string K_JWS_PAYLOAD = "payload";
+const std::string K_JWS_ALGORITHM = "algorithm";
+const std::string K_JWS_KEY_ID = "key-id";
+const std::string K_JWS_TYPE = "type";
+
+static void primop_jws_sign(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.jws-sign");
+
+    auto jws = primops->jwsSign(
+        instance(),
+        {
+            .key_identity = openssl_get_private_key_identity(state, pos, *args[0]),
+            .payload = getBytes(state, pos, K_JWS_PAYLOAD, *args[0]),
+            .algorithm = tryGetString(state, pos, K_JWS_ALGORITHM, *args[0]),
+            .key_id = tryGetString(state, pos, K_JWS_KEY_ID, *args[0]),
+            .media_type = tryGetString(state, pos, K_JWS_TYPE, *args[0])
+        }
+    );
+    result.mkString(jws);
+}
+
+const std::string K_JWT_CLAIMS = "claims";
+const std::string K_JWT_ISSUER = "issuer";
+const std::string K_JWT_SUBJECT = "subject";
+const std::string K_JWT_AUDIENCE = "audience";
+const std::string K_JWT_ISSUED_AT = "issued-at";
+const std::string K_JWT_NOT_BEFORE = "not-before";
+const std::string K_JWT_EXPIRES_AT = "expires-at";
+const std::string K_JWT_ID = "jwt-id";
+
+// Read the attribute 'key', an attribute set of claims whose
+// values are encoded as JSON. A missing attribute has no claims.
+static rust::Vec<JwtClaim> getJwtClaims(EvalState& state, const PosIdx pos, const std::string& key, Value& attrs) {
+
+    auto attr = attrs.attrs()->get(state.symbols.create(key));
+
+    if(!attr || !attr->value) {
+        return {};
+    }
+
+    Value& value = *attr->value;
+    state.forceAttrs(value, pos, std::format("while reading the value of the attribute '{}'", key));
+
+    rust::Vec<JwtClaim> result;
+    for(auto claim : *value.attrs()) {
+        std::string name(state.symbols[claim.name]);
+        NixStringContext context;
+        auto json = printValueAsJSON(state, true, *claim.value, pos, context, false).dump();
+        result.push_back({
+            .name = toRustString(state, pos, name, std::format("the name of a claim in '{}'", key)),
+            .value = toRustString(state, pos, json, std::format("the claim '{}'", name))
+        });
+    }
+
+    return result;
+}
+
+static void primop_jwt_sign(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.jwt-sign");
+
+    auto jwt = primops->jwtSign(
+        instance(),
+        {
+            .key_identity = openssl_get_private_key_identity(state, pos, *args[0]),
+            .issuer = tryGetString(state, pos, K_JWT_ISSUER, *args[0]),
+            .subject = tryGetString(state, pos, K_JWT_SUBJECT, *args[0]),
+            .audience = tryGetString(state, pos, K_JWT_AUDIENCE, *args[0]),
+            .issued_at = tryGetString(state, pos, K_JWT_ISSUED_AT, *args[0]),
+            .not_before = tryGetString(state, pos, K_JWT_NOT_BEFORE, *args[0]),
+            .expires_at = tryGetString(state, pos, K_JWT_EXPIRES_AT, *args[0]),
+            .jwt_id = tryGetString(state, pos, K_JWT_ID, *args[0]),
+            .claims = getJwtClaims(state, pos, K_JWT_CLAIMS, *args[0]),
+            .algorithm = tryGetString(state, pos, K_JWS_ALGORITHM, *args[0])
+        }
+    );
+    result.mkString(jwt);
+}
+
+const std::string K_JWKS_KEYS = "keys";
+
+static void primop_jwks(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.jwks");
+
+    auto keys = state.getAttr(state.symbols.create(K_JWKS_KEYS), args[0]->attrs(), "in the arguments of builtins.crypto.openssl.jwks");
+    state.forceList(*keys->value, pos, "while reading the 'keys' parameter");
+
+    JwksParams params;
+    for(auto key : keys->value->listView()) {
+        state.forceAttrs(*key, pos, "while reading a key of the 'keys' parameter");
+        params.keys.push_back({
+            .key_identity = openssl_get_private_key_identity(state, pos, *key),
+            .algorithm = tryGetString(state, pos, K_JWS_ALGORITHM, *key)
+        });
+    }
+
+    result.mkString(primops->jwks(instance(), std::move(params)));
+}
+
+const std::string K_VERIFY_PUBLIC_KEY = "public-key";
+const std::string K_VERIFY_SIGNATURE = "signature";
+
+// Read the string attribute 'key', which must be present.
+static rust::String getString(EvalState& state, const PosIdx pos, const std::string& key, Value& attrs) {
+
+    auto value = state.forceString(
+        *state.getAttr(state.symbols.create(key), attrs.attrs(), "in the openssl parameters")->value,
+        pos,
+        std::format("while reading the value of the attribute '{}'", key)
+    );
+
+    return toRustString(state, pos, value, std::format("the attribute '{}'", key));
+}
+
+// Verifying is pure, hence this primop does not use any CryptoNix instance.
+static void primop_openssl_verify(EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.verify");
+
+    auto valid = openssl_verify({
+        .public_key = getString(state, pos, K_VERIFY_PUBLIC_KEY, *args[0]),
+        .data = getBytes(state, pos, K_SIGN_DATA, *args[0]),
+        .signature = getString(state, pos, K_VERIFY_SIGNATURE, *args[0]),
+        .algorithm = tryGetString(state, pos, K_SIGN_ALGORITHM, *args[0]),
+        .encoding = tryGetString(state, pos, K_SIGN_ENCODING, *args[0])
     });
+    result.mkBool(valid);
+}
+
+const std::string K_X509_VERIFY_CERTIFICATE = "certificate";
+const std::string K_X509_VERIFY_ISSUER = "issuer";
+const std::string K_X509_VERIFY_DATE = "date";
 
-    auto opensslX509Pem = state.symbols.create(K_X509_PEM);
-    attrs.alloc(opensslX509Pem).mkPrimOp(new PrimOp { 
-        .name = K_X509_PEM,
-        .arity = 1,
-        .fun = primop_openssl_x509_pem
+static void primop_openssl_x509_verify(EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.x509-verify");
+
+    auto valid = openssl_x509_verify({
+        .certificate = getString(state, pos, K_X509_VERIFY_CERTIFICATE, *args[0]),
+        .issuer = getString(state, pos, K_X509_VERIFY_ISSUER, *args[0]),
+        .date = tryGetString(state, pos, K_X509_VERIFY_DATE, *args[0])
     });
+    result.mkBool(valid);
+}
+
+const std::string K_MINISIGN_ENCODING = "encoding";
+const std::string K_MINISIGN_DATA = "data";
+const std::string K_MINISIGN_UNTRUSTED_COMMENT = "untrusted-comment";
+const std::string K_MINISIGN_TRUSTED_COMMENT = "trusted-comment";
+
+static void primop_minisign_public_key(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.minisign-public-key");
+
+    auto publicKey = primops->minisignPublicKey(
+        instance(),
+        {
+            .key_identity = openssl_get_private_key_identity(state, pos, *args[0]),
+            .encoding = getString(state, pos, K_MINISIGN_ENCODING, *args[0])
+        }
+    );
+    result.mkString(publicKey);
+}
+
+static void primop_minisign_sign(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.minisign-sign");
+
+    auto signature = primops->minisignSign(
+        instance(),
+        {
+            .key_identity = openssl_get_private_key_identity(state, pos, *args[0]),
+            .data = getBytes(state, pos, K_MINISIGN_DATA, *args[0]),
+            .untrusted_comment = tryGetString(state, pos, K_MINISIGN_UNTRUSTED_COMMENT, *args[0]),
+            .trusted_comment = tryGetString(state, pos, K_MINISIGN_TRUSTED_COMMENT, *args[0])
+        }
+    );
+    result.mkString(signature);
+}
+
+const std::string K_NIX_CACHE_NAME = "name";
+const std::string K_NIX_CACHE_FINGERPRINT = "fingerprint";
+
+static NixCacheKeyParams nix_cache_get_key_params(EvalState& state, const PosIdx pos, Value& params) {
+
+    state.forceAttrs(params, pos, "while evaluating the Nix binary cache key parameters");
+
+    return {
+        .key_identity = openssl_get_private_key_identity(state, pos, params),
+        .name = getString(state, pos, K_NIX_CACHE_NAME, params)
+    };
+}
+
+static void primop_nix_cache_public_key(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    auto publicKey = primops->nixCachePublicKey(
+        instance(),
+        nix_cache_get_key_params(state, pos, *args[0])
+    );
+    result.mkString(publicKey);
+}
+
+static void primop_nix_cache_sign(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    auto signature = primops->nixCacheSign(
+        instance(),
+        {
+            .key = nix_cache_get_key_params(state, pos, *args[0]),
+            .fingerprint = getString(state, pos, K_NIX_CACHE_FINGERPRINT, *args[0])
+        }
+    );
+    result.mkString(signature);
+}
+
+const std::string K_PASSWORD_LENGTH = "length";
+const std::string K_PASSWORD_ALPHABET = "alphabet";
+const std::string K_PASSWORD_SCHEME = "scheme";
+
+static PasswordIdentity password_get_identity(EvalState& state, const PosIdx pos, Value& password_args) {
+
+    auto key_id = state.forceStringNoCtx(
+        *state.getAttr(
+            state.symbols.create("key-identity"),
+            password_args.attrs(),
+            "in the password parameters"
+        )->value,
+        pos,
+        "while reading the 'key-identity' parameter"
+    );
+
+    return {
+        .key_id = toRustString(state, pos, key_id, "the 'key-identity' parameter"),
+        .length = tryGetUInt32(state, pos, K_PASSWORD_LENGTH, password_args),
+        .alphabet = tryGetString(state, pos, K_PASSWORD_ALPHABET, password_args)
+    };
+}
+
+static void primop_password_hash(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.password-hash");
+
+    auto hash = primops->passwordHash(
+        instance(),
+        {
+            .password_identity = password_get_identity(state, pos, *args[0]),
+            .scheme = tryGetString(state, pos, K_PASSWORD_SCHEME, *args[0])
+        }
+    );
+    result.mkString(hash);
+}
+
+const std::string K_OPENPGP_PRIMARY_KEY = "primary-key";
+const std::string K_OPENPGP_SUBKEY = "subkey";
+const std::string K_OPENPGP_USER_ID = "user-id";
+const std::string K_OPENPGP_CREATION_TIME = "creation-time";
+const std::string K_OPENPGP_SIGN_DATA = "data";
+const std::string K_OPENPGP_SIGN_DATE = "date";
+
+static OpenpgpKeyParams openpgp_get_key_params(EvalState& state, const PosIdx pos, Value& params) {
+
+    state.forceAttrs(params, pos, "while evaluating the OpenPGP key parameters");
+
+    auto primaryKey = openssl_get_private_key_identity(
+        state,
+        pos,
+        *state.getAttr(
+            state.symbols.create(K_OPENPGP_PRIMARY_KEY),
+            params.attrs(),
+            std::format("while accessing the '{}' attribute.", K_OPENPGP_PRIMARY_KEY)
+        )->value
+    );
+
+    // A null subkey is treated as the attribute being absent
+    rust::Vec<OpensslPrivateKeyIdentity> subkey;
+    auto subkeyAttr = params.attrs()->get(state.symbols.create(K_OPENPGP_SUBKEY));
+    if(subkeyAttr && subkeyAttr->value) {
+        state.forceValue(*subkeyAttr->value, pos);
+        if(subkeyAttr->value->type() != nNull) {
+            subkey.push_back(openssl_get_private_key_identity(state, pos, *subkeyAttr->value));
+        }
+    }
+
+    return {
+        .primary_key_identity = std::move(primaryKey),
+        .subkey_identity = std::move(subkey),
+        .user_id = getString(state, pos, K_OPENPGP_USER_ID, params),
+        .creation_time = getString(state, pos, K_OPENPGP_CREATION_TIME, params)
+    };
+}
+
+static void primop_openpgp_public_key(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    auto certificate = primops->openpgpPublicKey(
+        instance(),
+        openpgp_get_key_params(state, pos, *args[0])
+    );
+    result.mkString(certificate);
+}
+
+static void primop_openpgp_fingerprint(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    auto fingerprint = primops->openpgpFingerprint(
+        instance(),
+        openpgp_get_key_params(state, pos, *args[0])
+    );
+    result.mkString(fingerprint);
+}
+
+static void primop_openpgp_sign(CryptoNixInstance instance, EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    state.forceAttrs(*args[0], pos, "while evaluating the arguments passed to builtins.crypto.openssl.openpgp-sign");
+
+    auto signature = primops->openpgpSign(
+        instance(),
+        {
+            .key = openpgp_get_key_params(state, pos, *args[0]),
+            .data = getBytes(state, pos, K_OPENPGP_SIGN_DATA, *args[0]),
+            .date = tryGetString(state, pos, K_OPENPGP_SIGN_DATE, *args[0])
+        }
+    );
+    result.mkString(signature);
+}
+
+// Every primop is registered together with its 'try-' variant
+constexpr const int OPENSSL_PRIMOPS_COUNT = 20 * 2;
+constexpr const std::string K_PUBLIC_KEY_PEM = "public-key-pem";
+constexpr const std::string K_PUBLIC_KEY = "public-key";
+constexpr const std::string K_X509_PEM = "x509-pem";
+constexpr const std::string K_SIGN = "sign";
+constexpr const std::string K_VERIFY = "verify";
+constexpr const std::string K_X509_VERIFY = "x509-verify";
+constexpr const std::string K_ENCRYPT = "encrypt";
+constexpr const std::string K_HMAC = "hmac";
+constexpr const std::string K_SYMMETRIC_ENCRYPT = "symmetric-encrypt";
+constexpr const std::string K_JWS_SIGN = "jws-sign";
+constexpr const std::string K_JWT_SIGN = "jwt-sign";
+constexpr const std::string K_JWKS = "jwks";
+constexpr const std::string K_MINISIGN_PUBLIC_KEY = "minisign-public-key";
+constexpr const std::string K_MINISIGN_SIGN = "minisign-sign";
+constexpr const std::string K_NIX_CACHE_PUBLIC_KEY = "nix-cache-public-key";
+constexpr const std::string K_NIX_CACHE_SIGN = "nix-cache-sign";
+constexpr const std::string K_PASSWORD_HASH = "password-hash";
+constexpr const std::string K_OPENPGP_PUBLIC_KEY = "openpgp-public-key";
+constexpr const std::string K_OPENPGP_FINGERPRINT = "openpgp-fingerprint";
+constexpr const std::string K_OPENPGP_SIGN = "openpgp-sign";
+
+// Build the 'openssl' attribute set. All the primops in the attribute
+// set operate on the CryptoNix 'instance' supplied to this function.
+static void primop_openssl(EvalState& state, CryptoNixInstance instance, Value& result) {
+
+    auto attrs = state.buildBindings(OPENSSL_PRIMOPS_COUNT);
+
+    addCryptoNixPrimOps(state, attrs, K_PUBLIC_KEY_PEM, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openssl_public_key_pem(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_PUBLIC_KEY, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openssl_public_key(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_X509_PEM, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openssl_x509_pem(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_SIGN, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openssl_sign(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_ENCRYPT, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openssl_encrypt(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_HMAC, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_hmac(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_SYMMETRIC_ENCRYPT, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_symmetric_encrypt(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_JWS_SIGN, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_jws_sign(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_JWT_SIGN, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_jwt_sign(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_JWKS, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_jwks(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_MINISIGN_PUBLIC_KEY, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_minisign_public_key(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_MINISIGN_SIGN, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_minisign_sign(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_NIX_CACHE_PUBLIC_KEY, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_nix_cache_public_key(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_NIX_CACHE_SIGN, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_nix_cache_sign(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_PASSWORD_HASH, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_password_hash(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_OPENPGP_PUBLIC_KEY, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openpgp_public_key(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_OPENPGP_FINGERPRINT, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openpgp_fingerprint(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_OPENPGP_SIGN, 1,
+        [instance](EvalState& state, const PosIdx pos, Value** args, Value& result) {
+            primop_openpgp_sign(instance, state, pos, args, result);
+        }
+    );
+
+    addCryptoNixPrimOps(state, attrs, K_VERIFY, 1, primop_openssl_verify);
+
+    addCryptoNixPrimOps(state, attrs, K_X509_VERIFY, 1, primop_openssl_x509_verify);
 
     result.mkAttrs(attrs);
 }
 
-#define CRYPTO_PRIMOPS_COUNT 2
+constexpr const std::string K_OPEN = "open";
+
+// Implementation of 'builtins.crypto.open'. The argument is an
+// attribute set of strings with the CryptoNix options (for example
+// 'mode' and 'store-path'). The result is the 'openssl' attribute
+// set bound to the CryptoNix instance configured with said options.
+static void primop_open(EvalState& state, const PosIdx pos, Value** args, Value& result) {
+
+    auto& options = *args[0];
+    state.forceAttrs(options, pos, "while evaluating the options passed to 'builtins.crypto.open'");
+
+    std::map<std::string, std::string> optionValues;
+    for(auto attr : *options.attrs()) {
+        std::string name(state.symbols[attr.name]);
+        auto value = state.forceStringNoCtx(
+            *attr.value,
+            pos,
+            std::format("while reading the option '{}' passed to 'builtins.crypto.open'", name)
+        );
+        toRustString(state, pos, value, std::format("the option '{}' passed to 'builtins.crypto.open'", name));
+        optionValues[name] = value;
+    }
+
+    primop_openssl(state, primops->open(optionValues), result);
+}
+
+#define CRYPTO_PRIMOPS_COUNT 4
 
 static void primop_crypto(EvalState& state, const PosIdx pos, Value** args, Value& result) {
     auto attrs = state.buildBindings(CRYPTO_PRIMOPS_COUNT);
 
     Value& openssl = attrs.alloc(state.symbols.create("openssl"));
-    primop_openssl(state, pos, args, openssl);
+    primop_openssl(state, primops->defaultInstance(), openssl);
 
     Value& age = attrs.alloc(state.symbols.create("age"));
     primop_age(state, pos, args, age);
 
+    addCryptoNixPrimOps(state, attrs, K_OPEN, 1, primop_open);
+
     result.mkAttrs(attrs);
 }
 
@@ -395,24 +1200,175 @@ rust::Box<CxxNixCrypto>& CryptoNixPrimops::cryptoNix() noexcept {
 
     if(!cryptoNixPtr) {
         cryptoNixPtr = std::make_unique<rust::Box<CxxNixCrypto>>(
-            nix_crypto_with_settings(cryptoNixSettings.extraCryptoNixArgs)
+            nix_crypto_with_settings({
+                .extra_args = rust::String::lossy(cryptoNixSettings.extraCryptoNixArgs.get()),
+                .mode = rust::String::lossy(cryptoNixSettings.cryptoNixMode.get()),
+                .store_path = rust::String::lossy(cryptoNixSettings.cryptoNixStorePath.get())
+            })
         );
     }
 
     return *cryptoNixPtr;
 }
 
-std::string CryptoNixPrimops::opensslPublicKeyPem(OpensslPrivateKeyIdentity&& key_identity) {
+CryptoNixInstance CryptoNixPrimops::defaultInstance() {
+
+    return [this]() -> CxxNixCrypto& {
+        return *cryptoNix();
+    };
+}
+
+CryptoNixInstance CryptoNixPrimops::open(const std::map<std::string, std::string>& options) {
+
+    // Nix strings cannot contain null characters, hence they
+    // are used as separators to build the key of the cache.
+    std::string cacheKey;
+    rust::Vec<CxxCryptoNixOption> cxxOptions;
+
+    for(auto& [key, value] : options) {
+        cacheKey.append(key).push_back('\0');
+        cacheKey.append(value).push_back('\0');
+        cxxOptions.push_back({ .key = rust::String(key), .value = rust::String(value) });
+    }
+
+    auto instance = openedInstances.find(cacheKey);
+    if(instance == openedInstances.end()) {
+        instance = openedInstances.emplace(cacheKey, nix_crypto_open(std::move(cxxOptions))).first;
+    }
+
+    CxxNixCrypto* instancePtr = &*instance->second;
+    return [instancePtr]() -> CxxNixCrypto& {
+        return *instancePtr;
+    };
+}
+
+std::string CryptoNixPrimops::opensslPublicKeyPem(CxxNixCrypto& instance, OpensslPrivateKeyIdentity&& key_identity) {
+
+    return std::string(
+        instance.cxx_openssl_private_key(key_identity)->public_pem().c_str()
+    );
+}
+
+std::string CryptoNixPrimops::opensslPublicKey(
+    CxxNixCrypto& instance,
+    OpensslPrivateKeyIdentity&& key_identity,
+    const rust::String& encoding
+) {
+
+    return std::string(
+        instance.cxx_openssl_private_key(key_identity)->public_key_encoded(encoding).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::opensslX509Pem(CxxNixCrypto& instance, X509BuildParams&& buildParams) {
+
+    return std::string(
+        instance.cxx_openssl_x509_certificate(buildParams)->public_pem().c_str()
+    );
+}
+
+std::string CryptoNixPrimops::opensslSign(CxxNixCrypto& instance, OpensslSignParams&& signParams) {
+
+    return std::string(
+        instance.cxx_openssl_sign(std::move(signParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::opensslEncrypt(CxxNixCrypto& instance, OpensslEncryptParams&& encryptParams) {
+
+    return std::string(
+        instance.cxx_openssl_encrypt(std::move(encryptParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::hmac(CxxNixCrypto& instance, HmacParams&& hmacParams) {
+
+    return std::string(
+        instance.cxx_hmac(std::move(hmacParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::symmetricEncrypt(CxxNixCrypto& instance, SymmetricEncryptParams&& encryptParams) {
+
+    return std::string(
+        instance.cxx_symmetric_encrypt(std::move(encryptParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::jwsSign(CxxNixCrypto& instance, JwsSignParams&& signParams) {
+
+    return std::string(
+        instance.cxx_jws_sign(std::move(signParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::jwtSign(CxxNixCrypto& instance, JwtSignParams&& signParams) {
+
+    return std::string(
+        instance.cxx_jwt_sign(std::move(signParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::jwks(CxxNixCrypto& instance, JwksParams&& jwksParams) {
+
+    return std::string(
+        instance.cxx_jwks(std::move(jwksParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::minisignPublicKey(CxxNixCrypto& instance, MinisignPublicKeyParams&& keyParams) {
+
+    return std::string(
+        instance.cxx_minisign_public_key(std::move(keyParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::minisignSign(CxxNixCrypto& instance, MinisignSignParams&& signParams) {
+
+    return std::string(
+        instance.cxx_minisign_sign(std::move(signParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::nixCachePublicKey(CxxNixCrypto& instance, NixCacheKeyParams&& keyParams) {
+
+    return std::string(
+        instance.cxx_nix_cache_public_key(std::move(keyParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::nixCacheSign(CxxNixCrypto& instance, NixCacheSignParams&& signParams) {
+
+    return std::string(
+        instance.cxx_nix_cache_sign(std::move(signParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::passwordHash(CxxNixCrypto& instance, PasswordHashParams&& hashParams) {
+
+    return std::string(
+        instance.cxx_password_hash(std::move(hashParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::openpgpPublicKey(CxxNixCrypto& instance, OpenpgpKeyParams&& keyParams) {
+
+    return std::string(
+        instance.cxx_openpgp_public_key(std::move(keyParams)).c_str()
+    );
+}
+
+std::string CryptoNixPrimops::openpgpFingerprint(CxxNixCrypto& instance, OpenpgpKeyParams&& keyParams) {
 
     return std::string(
-        cryptoNix()->cxx_openssl_private_key(key_identity)->public_pem().c_str()
+        instance.cxx_openpgp_fingerprint(std::move(keyParams)).c_str()
     );
 }
 
-std::string CryptoNixPrimops::opensslX509Pem(X509BuildParams&& buildParams) {
+std::string CryptoNixPrimops::openpgpSign(CxxNixCrypto& instance, OpenpgpSignParams&& signParams) {
 
     return std::string(
-        cryptoNix()->cxx_openssl_x509_certificate(buildParams)->public_pem().c_str()
+        instance.cxx_openpgp_sign(std::move(signParams)).c_str()
     );
 }
 
@@ -19,10 +19,23 @@ let
         boost
         cargo
         openssl
+        libxcrypt
         nix-main
       ]
     ;
   };
+  # The 'cryptonix' command, which manages the secrets of
+  # a store outside of Nix (for example to import keys).
+  nix-crypto-cli = pkgs.rustPlatform.buildRustPackage {
+    pname = "nix-crypto-cli";
+    version = "0.1.0";
+    src = ./.;
+    cargoLock.lockFile = ./Cargo.lock;
+    cargoBuildFlags = [ "-p" "nix_crypto_cli" ];
+    nativeBuildInputs = [ pkgs.pkg-config ];
+    doCheck = false;
+    buildInputs = [ pkgs.openssl pkgs.libxcrypt ];
+  };
   nix-crypto = 
     pkgs.stdenv.mkDerivation {
     	pname = "nix-crypto";
@@ -42,7 +55,7 @@ let
 in
   {
     packages = {
-      inherit nix-crypto-plugin nix-crypto;
+      inherit nix-crypto-plugin nix-crypto-cli nix-crypto;
       default = nix-crypto;
     };
   }
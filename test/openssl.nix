@@ -2,6 +2,7 @@
 let
   crypto = pkgs.callPackage ../crypto/default.nix {};
   inherit (crypto) openssl;
+  inherit (pkgs) lib;
 
   # Generates an openssl key or returns said key if already
   # in the vault. However, the private-key itself is not
@@ -14,6 +15,103 @@ let
     };
     type = "rsa";
   };
+
+  pk-ec = openssl.private-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-ec-key";
+    };
+    type = "ec-p384";
+  };
+
+  pk-ed25519 = openssl.private-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-ed25519-key";
+    };
+    type = "ed25519";
+  };
+
+  pk-x25519 = openssl.private-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-x25519-key";
+    };
+    type = "x25519";
+  };
+
+  release-pgp = openssl.openpgp-key {
+    primary-key = {
+      attrs = {
+        vault = "openssl";
+        name = "openssl-test-release-pgp-key";
+      };
+      type = "ed25519";
+    };
+    subkey = {
+      attrs = {
+        vault = "openssl";
+        name = "openssl-test-release-pgp-key";
+      };
+      type = "x25519";
+    };
+    user-id = "Release Signing <release@example.org>";
+    creation-time = "2024-01-01T00:00:00Z";
+  };
+
+  update-channel = openssl.minisign-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-update-channel-key";
+    };
+  };
+
+  binary-cache = openssl.nix-cache-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-binary-cache-key";
+    };
+    name = "cache.example.org-1";
+  };
+
+  alice-password = openssl.password {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-alice-password";
+    };
+  };
+
+  webhook-secret = openssl.symmetric-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-webhook-secret";
+    };
+  };
+
+  # The same attributes with different algorithm parameters
+  # must refer to a different key.
+  pk-rsa-3072 = openssl.private-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-key";
+    };
+    type = "rsa";
+    params = { bits = 3072; };
+  };
+
+  # A second store, opened explicitly, which must hold
+  # keys independent from the ones in the default store.
+  opened = crypto.open {
+    mode = "filesystem";
+    store-path = builtins.getEnv "CRYPTONIX_TEST_OPEN_STORE";
+  };
+  pk-rsa-opened = opened.openssl.private-key {
+    attrs = {
+      vault = "openssl";
+      name = "openssl-test-key";
+    };
+    type = "rsa";
+  };
 in
   {
     "It can generate a public/private key set" = { _assert, ... }:
@@ -33,5 +131,406 @@ in
         # safe to write into the nix store.
         _assert.strings.has-prefix "-----BEGIN CERTIFICATE-----" x509.certificate-pem
     ;
+    "It can sign a x509 certificate with an elliptic curve key" = { _assert, ... }:
+      let
+        x509 = pk-ec.x509 {
+          subject-name = { CN = "subject"; };
+          issuer-name = { CN = "issuer"; };
+          serial = 2;
+          start-date = "2026-01-09T21:29:36Z";
+          expiry-date = "2036-01-09T21:29:36Z";
+        };
+      in
+        _assert.strings.has-prefix "-----BEGIN CERTIFICATE-----" x509.certificate-pem
+    ;
+    "It can sign a x509 certificate with an Ed25519 key" = { _assert, ... }:
+      let
+        ca = pk-ed25519.x509 {
+          subject-name = { CN = "ca"; };
+          issuer-name = { CN = "ca"; };
+          serial = 3;
+          start-date = "2026-01-09T21:29:36Z";
+          expiry-date = "2036-01-09T21:29:36Z";
+          basic-constraints = { critical = true; ca = true; };
+          key-usage = { critical = true; key-cert-sign = true; };
+        };
+        leaf = pk-ed25519.x509 {
+          subject-public-key = pk-ec.public-key-pem;
+          subject-name = { CN = "leaf"; };
+          issuer-name = { CN = "ca"; };
+          serial = 6;
+          start-date = "2026-01-09T21:29:36Z";
+          expiry-date = "2036-01-09T21:29:36Z";
+        };
+        verify = certificate: issuer: openssl.x509-verify {
+          inherit certificate issuer;
+          date = "2030-01-01T00:00:00Z";
+        };
+      in
+        _assert
+          (verify leaf.certificate-pem ca.certificate-pem
+            && verify leaf.certificate-pem pk-ed25519.public-key-pem
+            && verify ca.certificate-pem ca.certificate-pem
+            && !(verify leaf.certificate-pem leaf.certificate-pem))
+          "Expected the leaf certificate to be issued by the Ed25519 CA"
+    ;
+    "It exports the raw public key of a X25519 key" = { _assert, ... }:
+      _assert
+        (builtins.stringLength pk-x25519.public-key-raw-base64 == 44
+          && builtins.stringLength pk-x25519.public-key-raw-hex == 64)
+        "Expected a 32 byte public key"
+    ;
+    "It refuses to sign certificates with a X25519 key" = { _assert, ... }:
+      let
+        result = builtins.crypto.openssl.try-x509-pem {
+          signing-private-key-identity = {
+            key-type = "x25519";
+            key-identity = "openssl-test-x25519-key";
+          };
+          subject-name = { CN = "subject"; };
+          issuer-name = { CN = "issuer"; };
+          serial = 4;
+          start-date = "2026-01-09T21:29:36Z";
+          expiry-date = "2036-01-09T21:29:36Z";
+        };
+      in
+        _assert
+          (!result.success && result.error.code == "invalid-argument")
+          "Expected an 'invalid-argument' error when signing with a key agreement key"
+    ;
+    "It exports the public key as an OpenSSH authorized_keys line" = { _assert, ... }:
+      _assert.strings.has-prefix "ssh-ed25519 " pk-ed25519.public-key-openssh
+    ;
+    "It exports the public key as a JSON Web Key" = { _assert, ... }:
+      _assert
+        (pk-ec.public-key-jwk.kty == "EC" && pk-ec.public-key-jwk.crv == "P-384")
+        "Expected an EC JSON Web Key on the P-384 curve"
+    ;
+    "It signs data with an Ed25519 key" = { _assert, ... }:
+      let
+        signature = pk-ed25519.sign { data = "release manifest"; encoding = "hex"; };
+      in
+        _assert
+          (builtins.stringLength signature == 128)
+          "Expected a 64 byte Ed25519 signature"
+    ;
+    "It returns the same signature when signing the same data again" = { _assert, ... }:
+      _assert
+        (pk-ec.sign { data = "release manifest"; } == pk-ec.sign { data = "release manifest"; }
+          && pk-rsa.sign { data = ./openssl.nix; } == pk-rsa.sign { data = ./openssl.nix; })
+        "Signing must not make evaluation impure"
+    ;
+    "It refuses signature algorithms which do not match the key type" = { _assert, ... }:
+      let
+        result = builtins.crypto.openssl.try-sign {
+          key-type = "ed25519";
+          key-identity = "openssl-test-ed25519-key";
+          data = "release manifest";
+          algorithm = "rsa-pss-sha256";
+        };
+      in
+        _assert
+          (!result.success && result.error.code == "invalid-argument")
+          "Expected an 'invalid-argument' error for an RSA algorithm with an Ed25519 key"
+    ;
+    "It verifies signatures against the public key" = { _assert, ... }:
+      let
+        signature = pk-ec.sign { data = "release manifest"; };
+        verify = data: openssl.verify {
+          public-key = pk-ec.public-key-pem;
+          inherit data signature;
+        };
+      in
+        _assert
+          (verify "release manifest" && !(verify "tampered manifest"))
+          "Expected only the signed data to be verified"
+    ;
+    "It verifies certificates against their issuer" = { _assert, ... }:
+      let
+        x509 = pk-rsa.x509 {
+          subject-name = { CN = "subject"; };
+          issuer-name = { CN = "subject"; };
+          serial = 5;
+          start-date = "2026-01-09T21:29:36Z";
+          expiry-date = "2036-01-09T21:29:36Z";
+        };
+        verify = issuer: date: openssl.x509-verify {
+          certificate = x509.certificate-pem;
+          inherit issuer date;
+        };
+      in
+        _assert
+          (verify pk-rsa.public-key-pem null
+            && verify x509.certificate-pem "2030-01-01T00:00:00Z"
+            && !(verify pk-rsa.public-key-pem "2040-01-01T00:00:00Z")
+            && !(verify pk-ec.public-key-pem null))
+          "Expected the certificate to be verified by its issuer only, within its validity"
+    ;
+    "It encrypts data to a key in a reproducible way" = { _assert, ... }:
+      let
+        ciphertext = pk-x25519.encrypt { plaintext = "database password"; };
+      in
+        _assert
+          (ciphertext == pk-x25519.encrypt { plaintext = "database password"; }
+            && ciphertext != pk-x25519.encrypt { plaintext = "another password"; }
+            && builtins.match "[A-Za-z0-9+/=]+" ciphertext != null)
+          "Expected the same base64 ciphertext for the same plaintext"
+    ;
+    "It refuses to encrypt to signing keys" = { _assert, ... }:
+      let
+        result = builtins.crypto.openssl.try-encrypt (pk-ed25519.key-ref // {
+          plaintext = "database password";
+        });
+      in
+        _assert
+          (!result.success && result.error.code == "invalid-argument")
+          "Expected an 'invalid-argument' error when encrypting to an Ed25519 key"
+    ;
+    "It computes a stable HMAC with a symmetric key" = { _assert, ... }:
+      let
+        mac = webhook-secret.hmac { message = "payload"; };
+      in
+        _assert
+          (mac == webhook-secret.hmac { message = "payload"; }
+            && mac != webhook-secret.hmac { message = "other payload"; }
+            && builtins.stringLength mac == 64
+            && builtins.stringLength (webhook-secret.hmac { message = "payload"; hash = "sha512"; }) == 128)
+          "Expected a stable hexadecimal HMAC of the size of the hash"
+    ;
+    "It derives distinct stable children from a symmetric key" = { _assert, ... }:
+      let
+        mac = key: key.hmac { message = "payload"; };
+        child = webhook-secret.derive "service";
+      in
+        _assert
+          (mac child == mac (webhook-secret.derive "service")
+            && mac child != mac webhook-secret
+            && mac child != mac (webhook-secret.derive "other-service")
+            && mac (child.derive "db") != mac child
+            && mac (child.derive "db") == mac ((webhook-secret.derive "service").derive "db"))
+          "Expected children to depend only on the root key and the labels"
+    ;
+    "It builds a JWK set identified by the JWK thumbprints" = { _assert, ... }:
+      let
+        jwks = openssl.jwks [ pk-ec { key = pk-rsa; algorithm = "PS256"; } pk-x25519 ];
+        kids = lib.map (key: key.kid) jwks.keys;
+      in
+        _assert
+          (kids == [ pk-ec.public-key-jwk-thumbprint pk-rsa.public-key-jwk-thumbprint pk-x25519.public-key-jwk-thumbprint ]
+            && lib.map (key: key.alg or null) jwks.keys == [ "ES384" "PS256" null ]
+            && (lib.elemAt jwks.keys 2).use == "enc")
+          "Expected one key per identity with its thumbprint as 'kid'"
+    ;
+    "It signs stable JWTs whose header names the key" = { _assert, ... }:
+      let
+        claims = {
+          issuer = "https://issuer.example";
+          subject = "bootstrap";
+          issued-at = "2026-01-01T00:00:00Z";
+          expires-at = "2036-01-01T00:00:00Z";
+          claims = { scope = [ "read" "write" ]; };
+        };
+        jwt = pk-ec.jwt claims;
+        parts = lib.splitString "." jwt;
+        signing-input = "${lib.elemAt parts 0}.${lib.elemAt parts 1}";
+        # An ES384 signature is 'r' and 's' as 48 bytes integers, i.e.
+        # 64 base64url characters each. 48 bytes being the size of a
+        # SHA-384 hash, 'builtins.convertHash' decodes them to hex.
+        to-hex = base64url: builtins.convertHash {
+          hash = builtins.replaceStrings [ "-" "_" ] [ "+" "/" ] base64url;
+          hashAlgo = "sha384";
+          toHashFormat = "base16";
+        };
+        der-length = hex: lib.fixedWidthString 2 "0" (lib.toLower (lib.toHexString (builtins.stringLength hex / 2)));
+        der-integer = hex:
+          if lib.hasPrefix "00" hex && builtins.stringLength hex > 2 then
+            der-integer (builtins.substring 2 (builtins.stringLength hex) hex)
+          else
+            let
+              positive = if lib.elem (builtins.substring 0 1 hex) [ "8" "9" "a" "b" "c" "d" "e" "f" ] then "00${hex}" else hex;
+            in
+              "02${der-length positive}${positive}";
+        der-signature = jws-signature:
+          let
+            integers = der-integer (to-hex (builtins.substring 0 64 jws-signature))
+              + der-integer (to-hex (builtins.substring 64 64 jws-signature));
+          in
+            "30${der-length integers}${integers}";
+        verify = data: openssl.verify {
+          public-key = pk-ec.public-key-pem;
+          algorithm = "ecdsa-sha384";
+          signature = der-signature (lib.elemAt parts 2);
+          encoding = "hex";
+          inherit data;
+        };
+        jws = pk-ed25519.jws { payload = ./openssl.nix; };
+        jws-parts = lib.splitString "." jws;
+      in
+        _assert
+          (jwt == pk-ec.jwt claims
+            && builtins.length parts == 3
+            && builtins.stringLength (lib.elemAt parts 2) == 128
+            && verify signing-input
+            && !(verify "${lib.elemAt parts 0}.${lib.elemAt parts 0}")
+            && openssl.verify {
+              public-key = pk-ed25519.public-key-pem;
+              data = "${lib.elemAt jws-parts 0}.${lib.elemAt jws-parts 1}";
+              signature = lib.elemAt jws-parts 2;
+              encoding = "base64url";
+            })
+          "Expected reproducible JWTs whose signature is checked by the public key"
+    ;
+    "It refuses JWS algorithms bound to another curve" = { _assert, ... }:
+      let
+        result = builtins.crypto.openssl.try-jwt-sign (pk-ec.key-ref // {
+          algorithm = "ES256";
+        });
+      in
+        _assert
+          (!result.success && result.error.code == "invalid-argument")
+          "Expected an 'invalid-argument' error for ES256 with a P-384 key"
+    ;
+    "It builds a reproducible OpenPGP certificate and signatures" = { _assert, ... }:
+      let
+        signature = release-pgp.sign { data = ./openssl.nix; };
+      in
+        _assert
+          (lib.hasPrefix "-----BEGIN PGP PUBLIC KEY BLOCK-----" release-pgp.public-key
+            && lib.hasPrefix "-----BEGIN PGP SIGNATURE-----" signature
+            && signature == release-pgp.sign { data = ./openssl.nix; }
+            && signature != release-pgp.sign { data = ./openssl.nix; date = "2025-01-01T00:00:00Z"; }
+            && builtins.match "[0-9A-F]{40}" release-pgp.fingerprint != null)
+          "Expected an armored certificate, a fingerprint and stable signatures"
+    ;
+    "It refuses OpenPGP primary keys which cannot sign" = { _assert, ... }:
+      let
+        result = builtins.crypto.openssl.try-openpgp-fingerprint {
+          primary-key = pk-x25519.key-ref;
+          user-id = "Nobody <nobody@example.org>";
+          creation-time = "2024-01-01T00:00:00Z";
+        };
+      in
+        _assert
+          (!result.success && result.error.code == "invalid-argument")
+          "Expected an 'invalid-argument' error for a x25519 primary key"
+    ;
+    "It signs data in the minisign format" = { _assert, ... }:
+      let
+        signature = update-channel.sign { data = ./openssl.nix; trusted-comment = "file:openssl.nix"; };
+        lines = lib.splitString "\n" signature;
+        # The 'ed25519' private key with the same attributes
+        plain-key = openssl.private-key {
+          attrs = {
+            vault = "openssl";
+            name = "openssl-test-update-channel-key";
+          };
+          type = "ed25519";
+        };
+      in
+        _assert
+          (lib.hasPrefix "RW" update-channel.public-key
+            && update-channel.signify-public-key == "untrusted comment: signify public key\n${update-channel.public-key}\n"
+            && builtins.elemAt lines 2 == "trusted comment: file:openssl.nix"
+            && signature == update-channel.sign { data = ./openssl.nix; trusted-comment = "file:openssl.nix"; }
+            && update-channel.public-key != builtins.crypto.openssl.public-key (plain-key.key-ref // { encoding = "minisign"; }))
+          "Expected minisign and signify public keys of a key reserved to minisign and a stable signature"
+    ;
+    "It signs narinfo fingerprints with a Nix binary cache key" = { _assert, ... }:
+      let
+        store-path = "/nix/store/02bfycjg1607gpcnsg8l13lc45qa8qj3-hello-2.12.1";
+        fingerprint = "1;${store-path};sha256:1l5ljyjk2jbbb5ahg01i1xg9fhqqm6gkf3jjfsb2x4d5vrirqsg8;226560;${store-path}";
+        signature = binary-cache.sign-path {
+          inherit store-path;
+          nar-hash = "sha256:1l5ljyjk2jbbb5ahg01i1xg9fhqqm6gkf3jjfsb2x4d5vrirqsg8";
+          nar-size = 226560;
+          references = [ store-path ];
+        };
+        public-key-base64 = lib.removePrefix "cache.example.org-1:" binary-cache.public-key;
+        # The 'SubjectPublicKeyInfo' of an Ed25519 key is a fixed header
+        # of 12 bytes followed by the raw key, so its base64 is the one
+        # of the header followed by the one of the key.
+        public-key-pem = "-----BEGIN PUBLIC KEY-----\nMCowBQYDK2VwAyEA${public-key-base64}\n-----END PUBLIC KEY-----\n";
+        verify = data: openssl.verify {
+          public-key = public-key-pem;
+          signature = lib.removePrefix "cache.example.org-1:" signature;
+          inherit data;
+        };
+        # The 'ed25519' private key with the same attributes
+        plain-key = openssl.private-key {
+          attrs = {
+            vault = "openssl";
+            name = "openssl-test-binary-cache-key";
+          };
+          type = "ed25519";
+        };
+      in
+        _assert
+          (lib.hasPrefix "cache.example.org-1:" binary-cache.public-key
+            && builtins.stringLength public-key-base64 == 44
+            && lib.hasPrefix "cache.example.org-1:" signature
+            && signature == binary-cache.sign-fingerprint fingerprint
+            && verify fingerprint
+            && !(verify "1;${store-path};sha256:1l5ljyjk2jbbb5ahg01i1xg9fhqqm6gkf3jjfsb2x4d5vrirqsg8;226561;${store-path}")
+            && public-key-base64 != plain-key.public-key-raw-base64)
+          "Expected a 'trusted-public-keys' entry of a key reserved to the cache and a narinfo signature checked by it"
+    ;
+    "It yields stable crypt hashes of generated passwords" = { _assert, ... }:
+      let
+        hash = scheme: alice-password.hash { inherit scheme; };
+        hashes = lib.map hash [ "yescrypt" "sha512-crypt" "bcrypt" "argon2" ];
+        # The salt is the third field of a crypt hash: '$6$<salt>$<hash>'
+        salt = hash: lib.elemAt (lib.splitString "$" hash) 2;
+      in
+        _assert
+          (lib.hasPrefix "$y$" alice-password.hashed-password
+            && alice-password.hashed-password == hash "yescrypt"
+            && lib.hasPrefix "$6$" (hash "sha512-crypt")
+            && lib.hasPrefix "$2b$12$" (hash "bcrypt")
+            && lib.hasPrefix "$argon2id$" (hash "argon2")
+            && builtins.length (lib.unique hashes) == 4
+            && builtins.stringLength (salt (hash "sha512-crypt")) == 16)
+          "Expected hashes in the crypt format of every scheme, each with its own salt"
+    ;
+    "It encrypts data deterministically with a symmetric key" = { _assert, ... }:
+      let
+        encrypt = key: associated-data: key.encrypt { plaintext = "api token"; inherit associated-data; };
+        ciphertext = encrypt webhook-secret null;
+      in
+        _assert
+          (ciphertext == encrypt webhook-secret null
+            && ciphertext != encrypt webhook-secret "app.conf"
+            && ciphertext != encrypt (webhook-secret.derive "service") null
+            && !(builtins.crypto.openssl ? symmetric-decrypt))
+          "Expected a stable ciphertext which cannot be decrypted from Nix"
+    ;
+    "It includes the algorithm parameters in the key identity" = { _assert, ... }:
+      _assert
+        (pk-rsa-3072.public-key-pem != pk-rsa.public-key-pem)
+        "Keys with different parameters must be different keys"
+    ;
+    "It reports errors as values via the try- primitives" = { _assert, ... }:
+      let
+        result = builtins.crypto.openssl.try-public-key-pem {
+          key-type = "not-a-key-type";
+          key-identity = "try-test";
+        };
+      in
+        _assert
+          (!result.success && result.error.code == "invalid-argument")
+          "Expected an 'invalid-argument' error for an unknown key type"
+    ;
+    "It reports configuration errors when opening a store" = { _assert, ... }:
+      let
+        result = builtins.crypto.try-open { mode = "not-a-mode"; };
+      in
+        _assert
+          (!result.success && result.error.code == "configuration")
+          "Expected a 'configuration' error for an unknown mode"
+    ;
+    "It can use an additional store opened explicitly" = { _assert, ... }:
+      _assert
+        (pk-rsa-opened.public-key-pem != pk-rsa.public-key-pem)
+        "The same key identity must refer to different keys in different stores"
+    ;
   }
 